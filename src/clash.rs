@@ -1,8 +1,10 @@
+mod builder;
 mod public_handle;
 mod testcase;
 
+pub use builder::ClashBuilder;
 pub use public_handle::PublicHandle;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use testcase::deserialize_testcases;
 pub use testcase::Testcase;
 
@@ -20,16 +22,92 @@ pub struct Clash {
     upvotes: i32,
     #[serde(rename = "downVotes")]
     downvotes: i32,
+    /// CodinGame nickname of the author of the clash.
+    #[serde(default)]
+    nickname: Option<String>,
+    /// Public handle of the author's CodinGame profile.
+    #[serde(rename = "codingamerHandle", default)]
+    codingamer_handle: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_topics")]
+    topics: Vec<String>,
+    #[serde(default)]
+    views: u32,
+    #[serde(rename = "commentCount", default)]
+    comment_count: u32,
+    #[serde(default)]
+    score: f64,
+    #[serde(rename = "statusHistory", default, deserialize_with = "deserialize_status_history")]
+    status_history: Vec<StatusChange>,
+    /// Current status of the clash. It is used when the status history is
+    /// empty, which is the case for clashes that were never moderated.
+    #[serde(default)]
+    status: Option<String>,
+    /// Version that is currently played, which can be older than the last
+    /// version if the latter has not been accepted yet.
+    #[serde(rename = "activeVersion", default)]
+    active_version: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-enum PuzzleType {
+/// An entry of the moderation history of a clash, e.g. when it got accepted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusChange {
+    /// Status the clash was moved to, e.g. `"ACCEPTED"`.
+    pub status: String,
+    /// Milliseconds since the Unix epoch.
+    #[serde(default)]
+    pub date: Option<u64>,
+}
+
+/// Kind of a contribution on CodinGame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PuzzleType {
+    /// Clash of Code.
     #[serde(rename = "CLASHOFCODE")]
     Clash,
+    /// Classic puzzle where the solution reads from STDIN and prints to
+    /// STDOUT.
     #[serde(rename = "PUZZLE_INOUT")]
     ClassicInOut,
 }
 
+// Topics are either plain strings or objects describing the topic, in which
+// case the handle of the topic is used.
+fn deserialize_topics<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TempTopic {
+        Name(String),
+        Handle { handle: String },
+        Other(serde::de::IgnoredAny),
+    }
+    let topics = Option::<Vec<TempTopic>>::deserialize(de)?.unwrap_or_default();
+    Ok(topics
+        .into_iter()
+        .filter_map(|topic| match topic {
+            TempTopic::Name(name) | TempTopic::Handle { handle: name } => Some(name),
+            TempTopic::Other(_) => None,
+        })
+        .collect())
+}
+
+// Entries without a status are skipped instead of failing the whole clash.
+fn deserialize_status_history<'de, D: Deserializer<'de>>(de: D) -> Result<Vec<StatusChange>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum TempStatusChange {
+        Change(StatusChange),
+        Other(serde::de::IgnoredAny),
+    }
+    let history = Option::<Vec<TempStatusChange>>::deserialize(de)?.unwrap_or_default();
+    Ok(history
+        .into_iter()
+        .filter_map(|change| match change {
+            TempStatusChange::Change(change) => Some(change),
+            TempStatusChange::Other(_) => None,
+        })
+        .collect())
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ClashVersion {
     version: u32,
@@ -66,6 +144,72 @@ struct ClashData {
 }
 
 impl Clash {
+    /// Starts building a clash from scratch, see [ClashBuilder].
+    pub fn builder(public_handle: PublicHandle, title: impl Into<String>) -> ClashBuilder {
+        ClashBuilder::new(public_handle, title)
+    }
+
+    pub fn public_handle(&self) -> &PublicHandle {
+        &self.public_handle
+    }
+
+    pub fn puzzle_type(&self) -> PuzzleType {
+        self.puzzle_type
+    }
+
+    pub fn upvotes(&self) -> i32 {
+        self.upvotes
+    }
+
+    pub fn downvotes(&self) -> i32 {
+        self.downvotes
+    }
+
+    /// Nickname of the author, if the clash JSON includes it.
+    pub fn author(&self) -> Option<&str> {
+        self.nickname.as_deref()
+    }
+
+    /// Handle of the author's CodinGame profile.
+    pub fn codingamer_handle(&self) -> Option<&str> {
+        self.codingamer_handle.as_deref()
+    }
+
+    pub fn topics(&self) -> &[String] {
+        &self.topics
+    }
+
+    pub fn views(&self) -> u32 {
+        self.views
+    }
+
+    pub fn comment_count(&self) -> u32 {
+        self.comment_count
+    }
+
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Moderation history of the clash, in the order CodinGame lists it.
+    pub fn status_history(&self) -> &[StatusChange] {
+        &self.status_history
+    }
+
+    /// The most recent status of the clash, e.g. `"ACCEPTED"`.
+    pub fn status(&self) -> Option<&str> {
+        let latest = self.status_history.iter().max_by_key(|change| change.date);
+        latest.map(|change| change.status.as_str()).or(self.status.as_deref())
+    }
+
+    pub fn active_version(&self) -> Option<u32> {
+        self.active_version
+    }
+
+    pub fn last_version(&self) -> u32 {
+        self.last_version.version
+    }
+
     pub fn testcases(&self) -> &Vec<Testcase> {
         &self.last_version.data.testcases
     }
@@ -114,3 +258,32 @@ impl Clash {
         self.is_reverse() && !self.is_fastest() && !self.is_shortest()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_is_the_latest_change() {
+        let contents = std::fs::read_to_string("fixtures/puzzles/stub_and_solution_tester.json").unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        json["statusHistory"] = serde_json::json!([
+            { "status": "ACCEPTED", "date": 2000 },
+            { "status": "PENDING", "date": 1000 },
+            "not a status change",
+        ]);
+        let clash: Clash = serde_json::from_value(json).unwrap();
+        assert_eq!(clash.status_history().len(), 2);
+        assert_eq!(clash.status(), Some("ACCEPTED"));
+        assert_eq!(clash.author(), Some("Rafarafa"));
+        assert_eq!(clash.views(), 38);
+        assert_eq!(clash.active_version(), Some(6));
+    }
+
+    #[test]
+    fn status_falls_back_to_the_current_status() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        assert!(clash.status_history().is_empty());
+        assert_eq!(clash.status(), Some("PENDING"));
+    }
+}
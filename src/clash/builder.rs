@@ -0,0 +1,196 @@
+use super::{Clash, ClashData, ClashVersion, PublicHandle, PuzzleType, Testcase};
+
+/// `ClashBuilder` creates a [Clash] from scratch, e.g. for clashes written
+/// locally instead of fetched from CodinGame. Serializing the built clash
+/// gives the same JSON format as CodinGame uses.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::{Clash, PublicHandle};
+/// use std::str::FromStr;
+///
+/// let handle = PublicHandle::from_str("c0ffee").unwrap();
+/// let clash = Clash::builder(handle, "Double it")
+///     .statement("Print [[n]] times two.")
+///     .fastest(true)
+///     .testcase("Small", "2", "4")
+///     .validator("Small", "3", "6")
+///     .build();
+/// assert_eq!(clash.testcases().len(), 2);
+/// assert_eq!(clash.testcases()[1].index, 2);
+/// ```
+#[derive(Debug)]
+pub struct ClashBuilder {
+    clash: Clash,
+}
+
+impl ClashBuilder {
+    pub fn new(public_handle: PublicHandle, title: impl Into<String>) -> Self {
+        let data = ClashData {
+            title: title.into(),
+            fastest: false,
+            reverse: false,
+            shortest: false,
+            statement: String::new(),
+            testcases: Vec::new(),
+            constraints: None,
+            stub_generator: None,
+            input_description: String::new(),
+            output_description: String::new(),
+        };
+        let clash = Clash {
+            id: 0,
+            public_handle,
+            last_version: ClashVersion {
+                version: 1,
+                data,
+                statement_html: None,
+            },
+            puzzle_type: PuzzleType::Clash,
+            upvotes: 0,
+            downvotes: 0,
+            nickname: None,
+            codingamer_handle: None,
+            topics: Vec::new(),
+            views: 0,
+            comment_count: 0,
+            score: 0.0,
+            status_history: Vec::new(),
+            status: None,
+            active_version: None,
+        };
+        ClashBuilder { clash }
+    }
+
+    /// Defaults to [PuzzleType::Clash].
+    pub fn puzzle_type(mut self, puzzle_type: PuzzleType) -> Self {
+        self.clash.puzzle_type = puzzle_type;
+        self
+    }
+
+    pub fn author(mut self, nickname: impl Into<String>) -> Self {
+        self.clash.nickname = Some(nickname.into());
+        self
+    }
+
+    pub fn topics(mut self, topics: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.clash.topics = topics.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Statement of the clash, using CodinGame formatting.
+    pub fn statement(mut self, statement: impl Into<String>) -> Self {
+        self.data().statement = statement.into();
+        self
+    }
+
+    pub fn input_description(mut self, description: impl Into<String>) -> Self {
+        self.data().input_description = description.into();
+        self
+    }
+
+    pub fn output_description(mut self, description: impl Into<String>) -> Self {
+        self.data().output_description = description.into();
+        self
+    }
+
+    pub fn constraints(mut self, constraints: impl Into<String>) -> Self {
+        self.data().constraints = Some(constraints.into());
+        self
+    }
+
+    /// Stub generator code used by [crate::stub::generate].
+    pub fn stub_generator(mut self, stub_generator: impl Into<String>) -> Self {
+        self.data().stub_generator = Some(stub_generator.into());
+        self
+    }
+
+    pub fn fastest(mut self, fastest: bool) -> Self {
+        self.data().fastest = fastest;
+        self
+    }
+
+    pub fn shortest(mut self, shortest: bool) -> Self {
+        self.data().shortest = shortest;
+        self
+    }
+
+    pub fn reverse(mut self, reverse: bool) -> Self {
+        self.data().reverse = reverse;
+        self
+    }
+
+    /// Adds a test, numbered after the testcases added so far.
+    pub fn testcase(
+        self,
+        title: impl Into<String>,
+        test_in: impl Into<String>,
+        test_out: impl Into<String>,
+    ) -> Self {
+        self.add_testcase(title.into(), test_in.into(), test_out.into(), false)
+    }
+
+    /// Adds a validator, numbered after the testcases added so far.
+    pub fn validator(
+        self,
+        title: impl Into<String>,
+        test_in: impl Into<String>,
+        test_out: impl Into<String>,
+    ) -> Self {
+        self.add_testcase(title.into(), test_in.into(), test_out.into(), true)
+    }
+
+    pub fn build(self) -> Clash {
+        self.clash
+    }
+
+    fn add_testcase(mut self, title: String, test_in: String, test_out: String, is_validator: bool) -> Self {
+        let testcases = &mut self.data().testcases;
+        testcases.push(Testcase {
+            index: testcases.len() + 1,
+            title,
+            test_in,
+            test_out,
+            is_validator,
+        });
+        self
+    }
+
+    fn data(&mut self) -> &mut ClashData {
+        &mut self.clash.last_version.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn built_clash_survives_a_json_round_trip() {
+        let clash = Clash::builder(PublicHandle::from_str("c0ffee").unwrap(), "Double it")
+            .puzzle_type(PuzzleType::ClassicInOut)
+            .author("someone")
+            .topics(["Loops"])
+            .statement("Print [[n]] times two.")
+            .stub_generator("read n:int\nwrite answer")
+            .shortest(true)
+            .testcase("Small", "2", "4")
+            .validator("Big", "1000", "2000")
+            .build();
+
+        let json = serde_json::to_string(&clash).unwrap();
+        let clash: Clash = serde_json::from_str(&json).unwrap();
+        assert_eq!(clash.title(), "Double it");
+        assert_eq!(clash.puzzle_type(), PuzzleType::ClassicInOut);
+        assert_eq!(clash.author(), Some("someone"));
+        assert_eq!(clash.topics(), ["Loops"]);
+        assert_eq!(clash.stub_generator(), Some("read n:int\nwrite answer"));
+        assert!(clash.is_shortest() && !clash.is_fastest());
+        let testcases = clash.testcases();
+        assert_eq!((testcases[1].index, testcases[1].is_validator), (2, true));
+        assert_eq!(testcases[1].test_out, "2000");
+    }
+}
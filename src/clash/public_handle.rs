@@ -18,7 +18,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 /// let invalid_handle = PublicHandle::from_str("xyz");
 /// assert!(invalid_handle.is_err());
 /// ```
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct PublicHandle(String);
 
 impl FromStr for PublicHandle {
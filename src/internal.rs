@@ -1,5 +1,37 @@
+mod clash_filter;
+mod clash_index;
+mod clash_project;
+mod config;
+mod custom_tests;
 mod formatter;
+mod history;
+mod json_file;
+mod lan;
 mod lines_with_endings;
+mod lint;
 mod outputstyle;
+mod personal_best;
+mod review;
+mod session;
+mod solution_archive;
+mod terminal;
+#[cfg(test)]
+mod test_helper;
+mod tui;
+mod watcher;
 
-pub use outputstyle::OutputStyle;
+pub use clash_filter::ClashFilter;
+pub use clash_index::{ClashIndex, IndexEntry};
+pub use clash_project::ClashProject;
+pub use config::{Config, LanguageConfig};
+pub use custom_tests::CustomTests;
+pub use history::{unix_timestamp, HistoryEntry, SolveHistory};
+pub use lan::{Game, Host, Message, Player, Submission};
+pub use lint::lint;
+pub use outputstyle::{clash_metadata, OutputStyle};
+pub use personal_best::PersonalBests;
+pub use review::{grade_solve, ReviewSchedule, FAILED};
+pub use session::{ClashSession, RoundRun};
+pub use solution_archive::SolutionArchive;
+pub use tui::Tui;
+pub use watcher::Watcher;
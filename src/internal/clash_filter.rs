@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+
+use clap::ArgMatches;
+use clashlib::clash::{Clash, PuzzleType};
+
+use super::clash_index::IndexEntry;
+
+/// Criteria for selecting stored clashes. Everything except the text search
+/// can be checked against the [ClashIndex](super::ClashIndex) alone.
+#[derive(Debug, Default)]
+pub struct ClashFilter {
+    pub fastest: bool,
+    pub shortest: bool,
+    pub reverse: bool,
+    pub puzzle_type: Option<PuzzleType>,
+    pub min_upvotes: Option<i32>,
+    pub max_downvotes: Option<i32>,
+    pub topic: Option<String>,
+    pub author: Option<String>,
+    pub status: Option<String>,
+    /// Handles of clashes that never match, e.g. the solved ones for
+    /// `next --unsolved`.
+    pub excluded: BTreeSet<String>,
+    /// Words that must all appear in the title or the statement (ignoring
+    /// case).
+    pub search_terms: Vec<String>,
+}
+
+impl ClashFilter {
+    /// Reads the filter arguments shared by `list` and `next`. Arguments that
+    /// a subcommand does not define are ignored.
+    pub fn from_args(args: &ArgMatches) -> Self {
+        let flag = |id: &str| args.try_get_one::<bool>(id).ok().flatten().copied().unwrap_or(false);
+        let value = |id: &str| args.try_get_one::<String>(id).ok().flatten().cloned();
+        ClashFilter {
+            fastest: flag("fastest"),
+            shortest: flag("shortest"),
+            reverse: flag("reverse"),
+            puzzle_type: value("type").map(|name| match name.as_str() {
+                "classic" => PuzzleType::ClassicInOut,
+                _ => PuzzleType::Clash,
+            }),
+            min_upvotes: args.try_get_one::<i32>("min-upvotes").ok().flatten().copied(),
+            max_downvotes: args.try_get_one::<i32>("max-downvotes").ok().flatten().copied(),
+            topic: value("topic"),
+            author: value("author"),
+            status: value("status"),
+            excluded: BTreeSet::new(),
+            search_terms: value("QUERY")
+                .map(|query| query.split_whitespace().map(str::to_lowercase).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Name of a puzzle type as accepted by `--type`.
+    pub fn type_name(puzzle_type: PuzzleType) -> &'static str {
+        match puzzle_type {
+            PuzzleType::Clash => "clash",
+            PuzzleType::ClassicInOut => "classic",
+        }
+    }
+
+    /// True if the clash matches everything except the search terms.
+    pub fn matches_entry(&self, entry: &IndexEntry) -> bool {
+        !self.excluded.contains(&entry.handle.to_string())
+            && entry.has_modes(self.fastest, self.shortest, self.reverse)
+            && self.puzzle_type.map_or(true, |puzzle_type| entry.puzzle_type == puzzle_type)
+            && self.min_upvotes.map_or(true, |min| entry.upvotes >= min)
+            && self.max_downvotes.map_or(true, |max| entry.downvotes <= max)
+            && self.topic.as_ref().map_or(true, |topic| {
+                entry.topics.iter().any(|entry_topic| entry_topic.eq_ignore_ascii_case(topic))
+            })
+            && matches_ignoring_case(self.author.as_deref(), entry.author.as_deref())
+            && matches_ignoring_case(self.status.as_deref(), entry.status.as_deref())
+    }
+
+    /// Whether [ClashFilter::matches_text] has to be checked, which requires
+    /// reading the whole clash.
+    pub fn has_search_terms(&self) -> bool {
+        !self.search_terms.is_empty()
+    }
+
+    pub fn matches_text(&self, clash: &Clash) -> bool {
+        let text = format!("{}\n{}", clash.title(), clash.statement()).to_lowercase();
+        self.search_terms.iter().all(|term| text.contains(term.as_str()))
+    }
+}
+
+/// True if there is no `wanted` value or it equals `actual` (ignoring case).
+fn matches_ignoring_case(wanted: Option<&str>, actual: Option<&str>) -> bool {
+    match (wanted, actual) {
+        (None, _) => true,
+        (Some(wanted), Some(actual)) => wanted.eq_ignore_ascii_case(actual),
+        (Some(_), None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_search_needs_all_terms() {
+        let clash = sample_clash();
+        let filter = |query: &str| ClashFilter {
+            search_terms: query.split_whitespace().map(str::to_lowercase).collect(),
+            ..Default::default()
+        };
+        assert!(filter("boggus").matches_text(&clash));
+        assert!(filter("BOGGUS asdf").matches_text(&clash));
+        assert!(!filter("boggus nothing").matches_text(&clash));
+    }
+
+    fn sample_clash() -> Clash {
+        let contents = std::fs::read_to_string("fixtures/puzzles/stub_and_solution_tester.json").unwrap();
+        serde_json::from_str(&contents).unwrap()
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use clashlib::clash::{Clash, PublicHandle, PuzzleType};
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// Summary of a locally stored clash, enough to filter clashes without
+/// reading their JSON files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub handle: PublicHandle,
+    pub title: String,
+    pub fastest: bool,
+    pub shortest: bool,
+    pub reverse: bool,
+    pub puzzle_type: PuzzleType,
+    pub upvotes: i32,
+    pub downvotes: i32,
+    pub topics: Vec<String>,
+    pub author: Option<String>,
+    /// Latest moderation status, e.g. `"ACCEPTED"`.
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Modification time of the clash file when it was indexed.
+    modified: SystemTime,
+}
+
+impl IndexEntry {
+    fn new(handle: PublicHandle, clash: &Clash, modified: SystemTime) -> Self {
+        IndexEntry {
+            handle,
+            title: clash.title().to_string(),
+            fastest: clash.is_fastest(),
+            shortest: clash.is_shortest(),
+            reverse: clash.is_reverse(),
+            puzzle_type: clash.puzzle_type(),
+            upvotes: clash.upvotes(),
+            downvotes: clash.downvotes(),
+            topics: clash.topics().to_vec(),
+            author: clash.author().map(String::from),
+            status: clash.status().map(String::from),
+            modified,
+        }
+    }
+
+    /// True if the clash has all of the given modes.
+    pub fn has_modes(&self, fastest: bool, shortest: bool, reverse: bool) -> bool {
+        (!fastest || self.fastest) && (!shortest || self.shortest) && (!reverse || self.reverse)
+    }
+}
+
+/// Index of the clashes in the clash directory, stored as JSON in the data
+/// directory. Entries are keyed by handle.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ClashIndex {
+    entries: BTreeMap<String, IndexEntry>,
+}
+
+impl ClashIndex {
+    /// Reads the index from `path`. A missing or unreadable index is treated
+    /// as empty, since it can always be rebuilt with [ClashIndex::sync].
+    pub fn load(path: &Path) -> Self {
+        json_file::load(path).unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    /// Adds or replaces the entry of the clash with `handle`, which is stored
+    /// in `clash_file`.
+    pub fn update(&mut self, handle: &PublicHandle, clash_file: &Path) -> Result<()> {
+        let modified = std::fs::metadata(clash_file)?.modified()?;
+        let contents = std::fs::read_to_string(clash_file)?;
+        let clash: Clash = serde_json::from_str(&contents)
+            .with_context(|| format!("Unable to deserialize clash from {:?}", clash_file))?;
+        self.entries
+            .insert(handle.to_string(), IndexEntry::new(handle.clone(), &clash, modified));
+        Ok(())
+    }
+
+    /// Brings the index up to date with the `.json` files in `clash_dir`:
+    /// new and modified files are (re)indexed and entries of removed files
+    /// are dropped. Files that can not be deserialized are left out. Returns
+    /// true if anything changed.
+    pub fn sync(&mut self, clash_dir: &Path) -> Result<bool> {
+        let mut on_disk = BTreeMap::new();
+        for dir_entry in std::fs::read_dir(clash_dir).with_context(|| "No clashes stored")? {
+            let path = dir_entry?.path();
+            let stem = path.file_stem().and_then(|stem| stem.to_str());
+            let Some(handle) = stem.and_then(|stem| PublicHandle::from_str(stem).ok()) else {
+                continue
+            };
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+                on_disk.insert(handle.to_string(), (handle, path, modified));
+            }
+        }
+
+        let num_entries = self.entries.len();
+        self.entries.retain(|handle, _| on_disk.contains_key(handle));
+        let mut changed = self.entries.len() != num_entries;
+
+        for (key, (handle, path, modified)) in on_disk {
+            let up_to_date = self.entries.get(&key).is_some_and(|entry| Some(entry.modified) == modified);
+            if !up_to_date {
+                // A broken clash file should not make every other clash
+                // unavailable, so it is just left out.
+                match self.update(&handle, &path) {
+                    Ok(()) => changed = true,
+                    Err(_) => changed |= self.entries.remove(&key).is_some(),
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::TempDir;
+
+    #[test]
+    fn sync_indexes_new_and_removed_files() {
+        let temp_dir = TempDir::new("index-test");
+        let dir = temp_dir.path();
+        let fixture = Path::new("fixtures/puzzles/stub_and_solution_tester.json");
+        let clash_file = dir.join("90435e82d1d5e3fe5f9d3dd813770f0d5a7d2.json");
+        std::fs::copy(fixture, &clash_file).unwrap();
+        std::fs::write(dir.join("abc.json"), "not a clash").unwrap();
+
+        let mut index = ClashIndex::default();
+        assert!(index.sync(dir).unwrap());
+        assert_eq!(index.len(), 1);
+        let entry = index.entries().next().unwrap();
+        assert_eq!(entry.title, "Boggus test");
+        assert_eq!(entry.author.as_deref(), Some("Rafarafa"));
+        assert_eq!(entry.puzzle_type, PuzzleType::Clash);
+
+        std::fs::remove_file(&clash_file).unwrap();
+        assert!(index.sync(dir).unwrap());
+        assert!(index.is_empty());
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::{Clash, PublicHandle, PuzzleType};
+use indoc::indoc;
+use serde::Deserialize;
+
+const MANIFEST: &str = "clash.toml";
+const STATEMENT: &str = "statement.md";
+const INPUT_DESCRIPTION: &str = "input.md";
+const OUTPUT_DESCRIPTION: &str = "output.md";
+const CONSTRAINTS: &str = "constraints.md";
+const STUB_GENERATOR: &str = "stub.txt";
+
+/// Contents of `clash.toml`, the metadata and testcases of a clash written
+/// locally.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Manifest {
+    handle: PublicHandle,
+    title: String,
+    /// `"clash"` or `"classic"`.
+    #[serde(rename = "type", default = "default_type")]
+    puzzle_type: String,
+    modes: Vec<String>,
+    author: Option<String>,
+    #[serde(default)]
+    topics: Vec<String>,
+    #[serde(default)]
+    testcases: Vec<ManifestTestcase>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ManifestTestcase {
+    title: String,
+    input: String,
+    output: String,
+    #[serde(default)]
+    validator: bool,
+}
+
+fn default_type() -> String {
+    String::from("clash")
+}
+
+/// A clash written locally, as a directory of files that are easy to edit:
+///
+/// ```text
+/// clash.toml      handle, title, type, modes, author, topics and testcases
+/// statement.md    statement, with CodinGame formatting
+/// input.md        description of the input
+/// output.md       description of the output
+/// constraints.md  constraints (optional)
+/// stub.txt        stub generator (optional)
+/// ```
+pub struct ClashProject {
+    dir: PathBuf,
+}
+
+impl ClashProject {
+    pub fn new(dir: PathBuf) -> Self {
+        ClashProject { dir }
+    }
+
+    /// Creates the files of a new clash, filled with an example that shows
+    /// the CodinGame formatting.
+    pub fn scaffold(&self, handle: &PublicHandle, title: &str) -> Result<()> {
+        if self.dir.join(MANIFEST).exists() {
+            return Err(anyhow!("{:?} already contains a clash", self.dir))
+        }
+        std::fs::create_dir_all(&self.dir).with_context(|| format!("Unable to create {:?}", self.dir))?;
+        let manifest = MANIFEST_TEMPLATE
+            .replace("{handle}", &handle.to_string())
+            .replace("{title}", &toml::Value::String(title.to_string()).to_string());
+        let files = [
+            (MANIFEST, manifest.as_str()),
+            (STATEMENT, STATEMENT_TEMPLATE),
+            (INPUT_DESCRIPTION, "<<Line 1:>> An integer [[n]]\n"),
+            (OUTPUT_DESCRIPTION, "<<Line 1:>> The value of [[n]] times two\n"),
+            (CONSTRAINTS, "-1000 ≤ [[n]] ≤ 1000\n"),
+            (STUB_GENERATOR, "read n:int\nwrite answer\n"),
+        ];
+        for (name, contents) in files {
+            let path = self.dir.join(name);
+            std::fs::write(&path, contents).with_context(|| format!("Unable to write {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// Assembles the files into a clash.
+    pub fn pack(&self) -> Result<Clash> {
+        let manifest_path = self.dir.join(MANIFEST);
+        let contents = std::fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Unable to read {:?}", manifest_path))?;
+        let manifest: Manifest =
+            toml::from_str(&contents).with_context(|| format!("Invalid clash file {:?}", manifest_path))?;
+
+        let puzzle_type = match manifest.puzzle_type.as_str() {
+            "clash" => PuzzleType::Clash,
+            "classic" => PuzzleType::ClassicInOut,
+            other => return Err(anyhow!("Unknown type {:?}, expected \"clash\" or \"classic\"", other)),
+        };
+        if let Some(mode) = manifest
+            .modes
+            .iter()
+            .find(|mode| !["fastest", "shortest", "reverse"].contains(&mode.as_str()))
+        {
+            return Err(anyhow!("Unknown mode {:?}, expected fastest, shortest or reverse", mode))
+        }
+        // The first testcase is shown as the example of the statement
+        if manifest.testcases.is_empty() {
+            return Err(anyhow!("{:?} has no testcases, add at least one [[testcases]]", manifest_path))
+        }
+        let has_mode = |mode: &str| manifest.modes.iter().any(|m| m == mode);
+
+        let mut builder = Clash::builder(manifest.handle.clone(), manifest.title.clone())
+            .puzzle_type(puzzle_type)
+            .topics(manifest.topics.clone())
+            .fastest(has_mode("fastest"))
+            .shortest(has_mode("shortest"))
+            .reverse(has_mode("reverse"))
+            .statement(self.read_text(STATEMENT)?.unwrap_or_default())
+            .input_description(self.read_text(INPUT_DESCRIPTION)?.unwrap_or_default())
+            .output_description(self.read_text(OUTPUT_DESCRIPTION)?.unwrap_or_default());
+        if let Some(author) = &manifest.author {
+            builder = builder.author(author);
+        }
+        if let Some(constraints) = self.read_text(CONSTRAINTS)? {
+            builder = builder.constraints(constraints);
+        }
+        if let Some(stub_generator) = self.read_text(STUB_GENERATOR)? {
+            builder = builder.stub_generator(stub_generator);
+        }
+        for testcase in manifest.testcases {
+            // Multi-line TOML strings usually end with a newline that is not
+            // part of the testcase
+            let input = trim_newline(testcase.input);
+            let output = trim_newline(testcase.output);
+            builder = if testcase.validator {
+                builder.validator(testcase.title, input, output)
+            } else {
+                builder.testcase(testcase.title, input, output)
+            };
+        }
+        Ok(builder.build())
+    }
+
+    /// Contents of one of the text files without trailing whitespace, `None`
+    /// if the file does not exist.
+    fn read_text(&self, name: &str) -> Result<Option<String>> {
+        let path = self.dir.join(name);
+        if !path.exists() {
+            return Ok(None)
+        }
+        let contents =
+            std::fs::read_to_string(&path).with_context(|| format!("Unable to read {:?}", path))?;
+        Ok(Some(contents.trim_end().to_string()))
+    }
+}
+
+fn trim_newline(mut text: String) -> String {
+    if text.ends_with('\n') {
+        text.pop();
+    }
+    text
+}
+
+const MANIFEST_TEMPLATE: &str = indoc! {r#"
+    handle = "{handle}"
+    title = {title}
+    # "clash" for a Clash of Code, "classic" for a classic puzzle
+    type = "clash"
+    modes = ["fastest", "shortest", "reverse"]
+    # author = "your CodinGame nickname"
+    topics = []
+
+    # Tests and validators, in the order CodinGame shows them
+    [[testcases]]
+    title = "Small number"
+    input = "3"
+    output = "6"
+
+    [[testcases]]
+    title = "Small number"
+    input = "4"
+    output = "8"
+    validator = true
+
+    [[testcases]]
+    title = "Negative number"
+    input = """
+    -12
+    """
+    output = """
+    -24
+    """
+
+    [[testcases]]
+    title = "Negative number"
+    input = "-15"
+    output = "-30"
+    validator = true
+"#};
+
+const STATEMENT_TEMPLATE: &str = indoc! {"
+    Given an integer [[n]], print <<twice>> its value.
+
+    Variables are written as [[n]], constants as {{1000}}, bold text as <<bold>>
+    and monospace text between backticks:
+    `n = 3
+    answer = 6`
+"};
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::internal::test_helper::TempDir;
+
+    #[test]
+    fn scaffolded_clash_can_be_packed() {
+        let temp_dir = TempDir::new("project-test");
+        let project = ClashProject::new(temp_dir.path().to_path_buf());
+        let handle = PublicHandle::from_str("c0ffee").unwrap();
+        project.scaffold(&handle, "Double \"it\"").unwrap();
+        assert!(project.scaffold(&handle, "Again").is_err());
+
+        let clash = project.pack().unwrap();
+        assert_eq!(clash.public_handle(), &handle);
+        assert_eq!(clash.title(), "Double \"it\"");
+        assert!(clash.is_fastest() && clash.is_shortest() && clash.is_reverse());
+        assert_eq!(clash.testcases().len(), 4);
+        assert_eq!(clash.testcases()[2].test_in, "-12");
+        assert!(clash.testcases()[3].is_validator);
+        assert_eq!(clash.stub_generator(), Some("read n:int\nwrite answer"));
+    }
+
+    #[test]
+    fn clash_without_testcases_is_rejected() {
+        let temp_dir = TempDir::new("project-test");
+        let manifest = "handle = \"c0ffee\"\ntitle = \"Empty\"\nmodes = [\"fastest\"]\n";
+        std::fs::write(temp_dir.path().join(MANIFEST), manifest).unwrap();
+        let error = ClashProject::new(temp_dir.path().to_path_buf()).pack().unwrap_err();
+        assert!(error.to_string().contains("no testcases"));
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// Contents of `coctus.toml` files.
+///
+/// ```toml
+/// default-language = "rust"
+///
+/// [languages.rust]
+/// build-command = "rustc -O sol.rs"
+/// command = "./sol"
+/// timeout = 10
+/// source = "sol.rs"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// Language used by `run` when neither --lang nor --command is given.
+    pub default_language: Option<String>,
+    #[serde(default)]
+    pub languages: BTreeMap<String, LanguageConfig>,
+}
+
+/// How to build and run solutions written in one language.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LanguageConfig {
+    pub build_command: Option<String>,
+    pub command: Option<String>,
+    /// Timeout in seconds (0 for no timeout).
+    pub timeout: Option<f64>,
+    /// Default source file of the solution.
+    pub source: Option<PathBuf>,
+}
+
+impl Config {
+    /// Reads and merges config files. Settings in later files take precedence
+    /// over earlier ones. Files that do not exist are skipped.
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut config = Config::default();
+        for path in paths.iter().filter(|path| path.is_file()) {
+            config.merge(Self::read(path)?);
+        }
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+        toml::from_str(&contents).with_context(|| format!("Invalid config file {:?}", path))
+    }
+
+    fn merge(&mut self, other: Config) {
+        if other.default_language.is_some() {
+            self.default_language = other.default_language;
+        }
+        for (name, other_lang) in other.languages {
+            let lang = self.languages.entry(name).or_default();
+            lang.build_command = other_lang.build_command.or(lang.build_command.take());
+            lang.command = other_lang.command.or(lang.command.take());
+            lang.timeout = other_lang.timeout.or(lang.timeout);
+            lang.source = other_lang.source.or(lang.source.take());
+        }
+    }
+
+    pub fn language(&self, name: &str) -> Result<&LanguageConfig> {
+        self.languages
+            .get(name)
+            .with_context(|| format!("No configuration for language {:?} in coctus.toml", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_config_overrides_earlier() {
+        let mut config: Config = toml::from_str(indoc::indoc! {r#"
+            default-language = "rust"
+
+            [languages.rust]
+            build-command = "rustc sol.rs"
+            command = "./sol"
+        "#})
+        .unwrap();
+        let local: Config = toml::from_str(indoc::indoc! {r#"
+            [languages.rust]
+            build-command = "rustc -O sol.rs"
+            timeout = 2
+        "#})
+        .unwrap();
+        config.merge(local);
+
+        let rust = config.language("rust").unwrap();
+        assert_eq!(config.default_language.as_deref(), Some("rust"));
+        assert_eq!(rust.build_command.as_deref(), Some("rustc -O sol.rs"));
+        assert_eq!(rust.command.as_deref(), Some("./sol"));
+        assert_eq!(rust.timeout, Some(2.0));
+    }
+
+    #[test]
+    fn unknown_language_is_an_error() {
+        assert!(Config::default().language("brainfuck").is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use clashlib::clash::{PublicHandle, Testcase};
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CustomTestcase {
+    title: String,
+    input: String,
+    output: String,
+}
+
+/// Testcases added with `coctus addtest`. They are stored next to the clash
+/// file in `<handle>.custom.json`, so that fetching the clash again does not
+/// lose them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CustomTests {
+    testcases: Vec<CustomTestcase>,
+}
+
+impl CustomTests {
+    pub fn path(clash_dir: &Path, handle: &PublicHandle) -> PathBuf {
+        clash_dir.join(format!("{}.custom.json", handle))
+    }
+
+    /// Reads the custom testcases from `path`. A missing file means there are
+    /// none.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn add(&mut self, title: String, input: String, output: String) {
+        self.testcases.push(CustomTestcase { title, input, output });
+    }
+
+    pub fn len(&self) -> usize {
+        self.testcases.len()
+    }
+
+    /// The custom testcases as regular tests, numbered after the
+    /// `num_clash_testcases` testcases of the clash. Their titles are marked
+    /// with `[custom]`.
+    pub fn to_testcases(&self, num_clash_testcases: usize) -> Vec<Testcase> {
+        (num_clash_testcases + 1..)
+            .zip(&self.testcases)
+            .map(|(index, custom)| Testcase {
+                index,
+                title: format!("{} [custom]", custom.title),
+                test_in: custom.input.clone(),
+                test_out: custom.output.clone(),
+                is_validator: false,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_testcases_are_numbered_after_the_clash() {
+        let mut custom = CustomTests::default();
+        custom.add(String::from("Empty"), String::new(), String::from("0"));
+        custom.add(String::from("Big"), String::from("1000"), String::from("1"));
+        let testcases = custom.to_testcases(8);
+        assert_eq!(testcases.len(), 2);
+        assert_eq!(testcases[1].index, 10);
+        assert_eq!(testcases[1].title, "Big [custom]");
+        assert!(!testcases[1].is_validator);
+    }
+}
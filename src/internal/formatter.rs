@@ -103,107 +103,123 @@ fn clean_line_size(line: &str) -> usize {
     line.len() - 4 * amount_tag_blocks
 }
 
-fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Vec<ansi_term::ANSIString<'a>> {
-    let mut parts = Vec::<ansi_term::ANSIString<'a>>::new();
+/// Formatting tag found by [scan_tags]. `pair` is the index of its tag pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tag {
+    Open { offset: usize, pair: usize },
+    Close { offset: usize, pair: usize },
+}
 
-    let mut cur_style = Style::default();
-    let mut buffer = String::new();
+/// Finds the formatting tags of `tag_pairs` in `text`. Returns the tags in
+/// order of their byte offset, and the offsets and descriptions of tags that
+/// are never closed or closed out of order.
+///
+/// Like on CodinGame, a tag that is already open can not be opened again
+/// (`<<<<Prompt>>>` is `<<Prompt` in bold followed by `>`), and tags that are
+/// never opened or never closed are plain text. Closing a tag also closes the
+/// tags opened after it.
+pub fn scan_tags(text: &str, tag_pairs: &[(&str, &str)]) -> (Vec<Tag>, Vec<(usize, String)>) {
+    let mut tags = Vec::new();
+    let mut problems = Vec::new();
+    // Opening tags that are not closed yet, with their index in `tags`
+    let mut stack: Vec<(usize, usize)> = Vec::new();
     let mut skip_until = 0;
-    let mut num_warnings = 0;
-    let mut stack: Vec<(Style, &str)> = vec![]; // Stack of (pre_style, opening_tag)
-
-    for (i, c) in text.char_indices() {
-        // Skip formatting tags by not adding them to the buffer.
+    for (i, _) in text.char_indices() {
         if i < skip_until {
-            continue;
+            continue
         }
-
         let slice = &text[i..];
-        for (style, tag_open, tag_close) in style_tag_pairs {
+        for (pair, (tag_open, tag_close)) in tag_pairs.iter().enumerate() {
             if slice.starts_with(tag_close) {
-                // Does this opening tag match the top of the stack?
-                if let Some((style, opening)) = stack.to_owned().last() {
-                    if opening == tag_open {
-                        stack.pop();
-                        // Paint and go back to the previous style
-                        parts.push(cur_style.paint(buffer.to_string()));
-                        buffer.clear();
-                        cur_style = *style;
-
-                        // Found a valid tag, skip it
-                        skip_until = i + tag_close.len();
-                        break
-                    } else {
-                        // Closing tag doesn't match the opening tag: ignore it and treat it as a normal
-                        // character
-                        // For example: `a\n>>b` (ok), or <<a[[b>>c]] (invalid).
-
-                        if num_warnings == 0 {
-                            eprintln!(
-                                "{} Bad formatting: tried to close {:?} with {:?}",
-                                Style::new().on(ansi_term::Color::Red).paint("WARNING"),
-                                opening,
-                                tag_close,
-                            );
-                        }
-                        num_warnings += 1;
+                if let Some(position) = stack.iter().rposition(|(_, open)| *open == pair) {
+                    for (index, open) in stack.drain(position + 1..) {
+                        let Tag::Open { offset, .. } = tags[index] else {
+                            unreachable!("only opening tags are on the stack")
+                        };
+                        let message = format!(
+                            "{:?} is closed by {:?} before its {:?}",
+                            tag_pairs[open].0, tag_close, tag_pairs[open].1
+                        );
+                        problems.push((offset, message));
                     }
+                    stack.pop();
+                    tags.push(Tag::Close { offset: i, pair });
+                    skip_until = i + tag_close.len();
+                    break
                 }
             }
-
             if slice.starts_with(tag_open) {
-                // There's definitely a tag to be parsed (grouped non-lazily)
-                if slice.contains(tag_close) {
-                    // NOTE (CG RULES):
-                    // Tags can not nest themselves:
-                    //     <<<<Prompt>>> => [<<Prompt]>>
-                    // So if the current open was already in the stack: ignore.
-
-                    // Paint the previous buffer with the previous colour
-                    // add it to the global "result" and then clear it
-                    parts.push(cur_style.paint(buffer.to_owned()));
-                    buffer.clear();
-                    // push cur_style to the stack to go back to it later on
-                    // then update the color to paint the next buffer
-                    stack.push((cur_style, tag_open));
-                    cur_style = nested_style(style, &cur_style);
-
-                    // Found a valid tag, skip it
-                    skip_until = i + tag_open.len();
-                } else {
-                    // Opening tag that is never closed: ignore it and treat it as a normal
-                    // character
-                    if num_warnings == 0 {
-                        eprintln!(
-                            "{} Bad formatting: ignoring {:?} that is never closed",
-                            Style::new().on(ansi_term::Color::Red).paint("WARNING"),
-                            tag_open
-                        );
-                    }
-                    num_warnings += 1;
+                if !stack.iter().any(|(_, open)| *open == pair) {
+                    stack.push((tags.len(), pair));
+                    tags.push(Tag::Open { offset: i, pair });
                 }
+                skip_until = i + tag_open.len();
                 break
             }
         }
-        if i >= skip_until {
-            buffer.push(c);
-        }
     }
 
-    for (_, tag_open) in stack {
-        // Opening tag was never closed
-        if num_warnings == 0 {
-            eprintln!(
-                "{} Bad formatting: {:?} was never closed",
-                Style::new().on(ansi_term::Color::Red).paint("WARNING"),
-                tag_open
-            );
+    // Tags that are never closed are plain text
+    for &(index, pair) in stack.iter().rev() {
+        let Tag::Open { offset, .. } = tags.remove(index) else {
+            unreachable!("only opening tags are on the stack")
+        };
+        let (tag_open, tag_close) = tag_pairs[pair];
+        problems.push((offset, format!("{:?} is never closed with {:?}", tag_open, tag_close)));
+    }
+    problems.sort_by_key(|(offset, _)| *offset);
+    (tags, problems)
+}
+
+fn paint_parts<'a>(text: &'a str, style_tag_pairs: &[(Style, &str, &str)]) -> Vec<ansi_term::ANSIString<'a>> {
+    let tag_pairs: Vec<(&str, &str)> = style_tag_pairs
+        .iter()
+        .map(|(_, tag_open, tag_close)| (*tag_open, *tag_close))
+        .collect();
+    let (tags, problems) = scan_tags(text, &tag_pairs);
+    if let Some((_, message)) = problems.first() {
+        eprintln!(
+            "{} Bad formatting: {}",
+            Style::new().on(ansi_term::Color::Red).paint("WARNING"),
+            message
+        );
+    }
+
+    let mut parts = Vec::<ansi_term::ANSIString<'a>>::new();
+    let mut cur_style = Style::default();
+    let mut stack: Vec<(Style, usize)> = vec![]; // Stack of (pre_style, pair)
+    let mut painted_until = 0;
+
+    for tag in tags {
+        // Paint the text before the tag, then skip the tag itself
+        let (offset, pair, tag_len) = match tag {
+            Tag::Open { offset, pair } => (offset, pair, tag_pairs[pair].0.len()),
+            Tag::Close { offset, pair } => (offset, pair, tag_pairs[pair].1.len()),
+        };
+        parts.push(cur_style.paint(&text[painted_until..offset]));
+        painted_until = offset + tag_len;
+
+        match tag {
+            Tag::Open { .. } => {
+                // push cur_style to the stack to go back to it later on
+                // then update the color to paint the next buffer
+                stack.push((cur_style, pair));
+                cur_style = nested_style(&style_tag_pairs[pair].0, &cur_style);
+            }
+            Tag::Close { .. } => {
+                // Go back to the style from before the matching opening tag
+                while let Some((style, open)) = stack.pop() {
+                    cur_style = style;
+                    if open == pair {
+                        break
+                    }
+                }
+            }
         }
-        num_warnings += 1;
     }
 
-    if !buffer.is_empty() {
-        parts.push(cur_style.paint(buffer.to_string()));
+    if painted_until < text.len() {
+        parts.push(cur_style.paint(&text[painted_until..]));
     }
 
     parts
@@ -394,6 +410,17 @@ mod tests {
         assert_eq!(parts.len(), 5);
     }
 
+    #[test]
+    fn painting_follows_codingame_tag_rules() {
+        let bold = Style::default().bold();
+        let tag_pairs = vec![(bold, "<<", ">>")];
+
+        let parts = paint_parts("<<<<Prompt>>> a << b", &tag_pairs);
+        assert_eq!(parts[1], bold.paint("<<Prompt"));
+        assert_eq!(parts[2], ansi_term::ANSIString::from("> a << b"));
+        assert_eq!(parts.len(), 3);
+    }
+
     #[test]
     /// Test formatting that really shouldn't exist – it doesn't really matter
     /// what the output is (since the formatting is not well-defined anyway)
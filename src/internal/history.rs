@@ -0,0 +1,208 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use clashlib::clash::PublicHandle;
+use serde::{Deserialize, Serialize};
+
+use super::clash_index::ClashIndex;
+use super::json_file;
+
+/// Outcome of one `coctus run`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub handle: PublicHandle,
+    /// Name of the language from the config, or the command that ran the
+    /// solution.
+    pub language: String,
+    /// Number of passed testcases (tests and validators).
+    pub passed: usize,
+    /// Number of testcases of the clash, including the ones that were not run.
+    pub total: usize,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    /// Time spent running the testcases.
+    #[serde(with = "duration_secs")]
+    pub duration: Duration,
+    /// Game mode the solution was written for (fastest, shortest or reverse).
+    pub mode: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(handle: PublicHandle, language: String, passed: usize, total: usize) -> Self {
+        HistoryEntry {
+            handle,
+            language,
+            passed,
+            total,
+            timestamp: unix_timestamp(SystemTime::now()),
+            duration: Duration::ZERO,
+            mode: None,
+        }
+    }
+
+    /// True if every testcase of the clash passed.
+    pub fn is_solved(&self) -> bool {
+        self.total > 0 && self.passed == self.total
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+mod duration_secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(de: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_secs_f64(f64::deserialize(de)?.max(0.0)))
+    }
+}
+
+/// Every recorded run, oldest first. Stored as JSON Lines (one entry per line)
+/// so that recording a run only appends to the file.
+#[derive(Debug, Default)]
+pub struct SolveHistory {
+    entries: Vec<HistoryEntry>,
+}
+
+impl SolveHistory {
+    /// Reads the history from `path`. A missing file means nothing has been
+    /// recorded yet. Lines that can not be deserialized are skipped.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(SolveHistory::default())
+        }
+        let contents = std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+        let entries = contents.lines().filter_map(|line| serde_json::from_str(line).ok()).collect();
+        Ok(SolveHistory { entries })
+    }
+
+    /// Appends `entry` to the history file at `path`.
+    pub fn record(path: &Path, entry: &HistoryEntry) -> Result<()> {
+        json_file::create_parent_dir(path)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Unable to open {:?}", path))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)
+            .with_context(|| format!("Unable to write {:?}", path))
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Handles of the clashes that were solved at least once.
+    pub fn solved_handles(&self) -> BTreeSet<String> {
+        let solved = self.entries.iter().filter(|entry| entry.is_solved());
+        solved.map(|entry| entry.handle.to_string()).collect()
+    }
+
+    /// Number of attempted and solved clashes, grouped by the mode of the
+    /// runs and by the topics of the clashes (as far as they are in `index`).
+    pub fn stats(&self, index: &ClashIndex) -> SolveStats {
+        let topics_of: BTreeMap<String, &[String]> = index
+            .entries()
+            .map(|entry| (entry.handle.to_string(), entry.topics.as_slice()))
+            .collect();
+
+        let mut stats = SolveStats {
+            runs: self.entries.len(),
+            ..Default::default()
+        };
+        for entry in &self.entries {
+            let handle = entry.handle.to_string();
+            let mode = entry.mode.clone().unwrap_or_else(|| String::from("-"));
+            let topics = topics_of.get(&handle).copied().unwrap_or_default();
+            stats.overall.record(&handle, entry.is_solved());
+            stats.by_mode.entry(mode).or_default().record(&handle, entry.is_solved());
+            for topic in topics {
+                stats.by_topic.entry(topic.clone()).or_default().record(&handle, entry.is_solved());
+            }
+        }
+        stats
+    }
+}
+
+/// Summary of a [SolveHistory].
+#[derive(Debug, Default)]
+pub struct SolveStats {
+    pub runs: usize,
+    pub overall: SolveCount,
+    pub by_mode: BTreeMap<String, SolveCount>,
+    pub by_topic: BTreeMap<String, SolveCount>,
+}
+
+/// Distinct clashes that were attempted and solved.
+#[derive(Debug, Default)]
+pub struct SolveCount {
+    attempted: BTreeSet<String>,
+    solved: BTreeSet<String>,
+}
+
+impl SolveCount {
+    fn record(&mut self, handle: &str, solved: bool) {
+        self.attempted.insert(handle.to_string());
+        if solved {
+            self.solved.insert(handle.to_string());
+        }
+    }
+
+    pub fn attempted(&self) -> usize {
+        self.attempted.len()
+    }
+
+    pub fn solved(&self) -> usize {
+        self.solved.len()
+    }
+
+    /// Fraction of the attempted clashes that were solved.
+    pub fn solve_rate(&self) -> f64 {
+        if self.attempted.is_empty() {
+            0.0
+        } else {
+            self.solved.len() as f64 / self.attempted.len() as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn stats_count_distinct_clashes() {
+        let entry = |handle: &str, passed: usize, mode: Option<&str>| HistoryEntry {
+            mode: mode.map(String::from),
+            ..HistoryEntry::new(PublicHandle::from_str(handle).unwrap(), String::from("py"), passed, 3)
+        };
+        let history = SolveHistory {
+            entries: vec![
+                entry("abc1", 1, Some("fastest")),
+                entry("abc1", 3, Some("fastest")),
+                entry("abc2", 2, Some("shortest")),
+                entry("abc2", 2, None),
+            ],
+        };
+        let stats = history.stats(&ClashIndex::default());
+        assert_eq!(stats.runs, 4);
+        assert_eq!((stats.overall.attempted(), stats.overall.solved()), (2, 1));
+        assert_eq!(stats.by_mode["fastest"].solve_rate(), 1.0);
+        assert_eq!(stats.by_mode["shortest"].solved(), 0);
+        assert_eq!(stats.by_mode["-"].attempted(), 1);
+        assert_eq!(history.solved_handles(), BTreeSet::from([String::from("abc1")]));
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads a `T` from the JSON file at `path`. A missing file is read as
+/// `T::default()`, so that state files only need to exist once something was
+/// stored in them.
+pub fn load<T: DeserializeOwned + Default>(path: &Path) -> Result<T> {
+    if !path.exists() {
+        return Ok(T::default())
+    }
+    let contents = std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?;
+    serde_json::from_str(&contents).with_context(|| format!("Unable to deserialize {:?}", path))
+}
+
+/// Writes `value` as JSON to `path`, creating its directory if needed.
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    create_parent_dir(path)?;
+    let contents = serde_json::to_string_pretty(value)?;
+    std::fs::write(path, contents).with_context(|| format!("Unable to write {:?}", path))
+}
+
+pub fn create_parent_dir(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("Unable to create {:?}", dir))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::internal::test_helper::TempDir;
+
+    #[test]
+    fn saved_values_load_back() {
+        let temp_dir = TempDir::new("json-file-test");
+        let path = temp_dir.path().join("state").join("values.json");
+        assert_eq!(load::<BTreeMap<String, u32>>(&path).unwrap(), BTreeMap::new());
+
+        let values = BTreeMap::from([(String::from("a"), 1), (String::from("b"), 2)]);
+        save(&path, &values).unwrap();
+        assert_eq!(load::<BTreeMap<String, u32>>(&path).unwrap(), values);
+
+        std::fs::write(&path, "not json").unwrap();
+        assert!(load::<BTreeMap<String, u32>>(&path).is_err());
+    }
+}
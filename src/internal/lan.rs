@@ -0,0 +1,395 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::Clash;
+use serde::{Deserialize, Serialize};
+
+/// Messages exchanged between the host and the players of a LAN game, sent as
+/// one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Sent by a player right after connecting.
+    Join { name: String },
+    /// Sent by the host to a player that joined.
+    Clash(Game),
+    /// Sent by a player, replaces the previous submission of that player.
+    Submit(Submission),
+    /// Sent by the host to every player whenever a submission comes in.
+    Leaderboard { standings: Vec<Standing> },
+    /// Sent by the host when it can not handle a message.
+    Error { message: String },
+}
+
+/// What the players of a LAN game play.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Game {
+    /// Contents of the clash file, exactly as stored by the host.
+    pub clash: String,
+    /// Game mode: fastest, shortest or reverse.
+    pub mode: String,
+    /// Time limit in minutes.
+    pub minutes: u64,
+}
+
+/// Result of a player's solution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Submission {
+    pub passed: usize,
+    pub total: usize,
+    pub code_length: Option<usize>,
+}
+
+impl Submission {
+    /// Percentage of passed testcases.
+    pub fn score(&self) -> usize {
+        (self.passed * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+/// A player on the leaderboard with their latest submission.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    pub submission: Option<Submission>,
+    /// Seconds from the start of the round until the latest submission, as
+    /// measured by the host.
+    pub elapsed: u64,
+}
+
+/// Sorts players like the ranking of a Clash of Code: higher scores first,
+/// then shorter code in shortest mode, then faster submissions. Players that
+/// did not submit yet come last.
+pub fn rank(standings: &mut [Standing], mode: &str) {
+    standings.sort_by_key(|standing| match &standing.submission {
+        Some(submission) => {
+            let code_length = if mode == "shortest" {
+                submission.code_length
+            } else {
+                None
+            };
+            (
+                0,
+                usize::MAX - submission.score(),
+                code_length.unwrap_or(usize::MAX),
+                standing.elapsed,
+            )
+        }
+        None => (1, 0, 0, 0),
+    });
+}
+
+pub fn send(stream: &mut TcpStream, message: &Message) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Reads the messages sent over a stream.
+pub struct Receiver {
+    reader: BufReader<TcpStream>,
+}
+
+impl Receiver {
+    pub fn new(stream: TcpStream) -> Self {
+        Receiver {
+            reader: BufReader::new(stream),
+        }
+    }
+
+    /// Waits for the next message. Returns `None` when the connection is
+    /// closed.
+    pub fn receive(&mut self) -> Result<Option<Message>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None)
+        }
+        let message = serde_json::from_str(&line).with_context(|| format!("Invalid message: {:?}", line))?;
+        Ok(Some(message))
+    }
+}
+
+#[derive(Default)]
+struct Lobby {
+    /// Players in the order they joined, with the stream to send them
+    /// messages.
+    players: Vec<(Standing, TcpStream)>,
+}
+
+impl Lobby {
+    /// Locks the lobby even if the thread of another player panicked while
+    /// holding it, so that one player can not end the game for everyone.
+    fn lock(lobby: &Mutex<Lobby>) -> MutexGuard<'_, Lobby> {
+        lobby.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn standings(&self, mode: &str) -> Vec<Standing> {
+        let mut standings: Vec<Standing> =
+            self.players.iter().map(|(standing, _)| standing.clone()).collect();
+        rank(&mut standings, mode);
+        standings
+    }
+
+    /// Sends the leaderboard to every player that is still connected.
+    fn broadcast(&mut self, mode: &str) -> Vec<Standing> {
+        let standings = self.standings(mode);
+        let message = Message::Leaderboard {
+            standings: standings.clone(),
+        };
+        for (_, stream) in self.players.iter_mut() {
+            // Players that left are simply no longer updated
+            let _ = send(stream, &message);
+        }
+        standings
+    }
+}
+
+/// The host of a LAN game: hands out a clash to every player that joins and
+/// keeps the leaderboard.
+pub struct Host {
+    listener: TcpListener,
+    game: Game,
+    /// Number of testcases of the clash, which every submission must report.
+    num_testcases: usize,
+}
+
+impl Host {
+    /// Listens on `addr`, e.g. `0.0.0.0:4242`.
+    pub fn bind(addr: impl ToSocketAddrs, game: Game) -> Result<Self> {
+        let clash: Clash = serde_json::from_str(&game.clash).context("Unable to deserialize the clash")?;
+        let num_testcases = clash.testcases().len();
+        let listener = TcpListener::bind(addr).context("Unable to listen for players")?;
+        Ok(Host {
+            listener,
+            game,
+            num_testcases,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Starts the round and accepts players until the process is stopped.
+    /// The time limit counts from the start of the round for every player, no
+    /// matter when they join. `on_update` is called with the leaderboard
+    /// whenever a player joins or submits.
+    pub fn serve(self, on_update: impl Fn(&[Standing]) + Send + Sync + 'static) -> Result<()> {
+        let round = Round {
+            game: self.game,
+            num_testcases: self.num_testcases,
+            started: Instant::now(),
+        };
+        let round = Arc::new(round);
+        let lobby = Arc::new(Mutex::new(Lobby::default()));
+        let on_update = Arc::new(on_update);
+        for stream in self.listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let (lobby, on_update, round) = (Arc::clone(&lobby), Arc::clone(&on_update), Arc::clone(&round));
+            std::thread::spawn(move || {
+                // A player that disconnects or misbehaves only ends their own thread
+                let _ = handle_player(stream, &lobby, &round, &*on_update);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The round being played, shared by the threads of all players.
+struct Round {
+    game: Game,
+    /// Number of testcases of the clash, which every submission must report.
+    num_testcases: usize,
+    started: Instant,
+}
+
+impl Round {
+    /// Checks a submission that arrived `elapsed` after the start of the round.
+    fn check_submission(&self, submission: &Submission, elapsed: Duration) -> Result<(), String> {
+        if elapsed > Duration::from_secs(self.game.minutes.saturating_mul(60)) {
+            return Err(format!("Time is up, the game only lasted {} minutes", self.game.minutes))
+        }
+        if submission.total != self.num_testcases || submission.passed > submission.total {
+            return Err(format!(
+                "Invalid submission: {}/{} passed testcases but the clash has {}",
+                submission.passed, submission.total, self.num_testcases
+            ))
+        }
+        Ok(())
+    }
+}
+
+fn handle_player(
+    stream: TcpStream,
+    lobby: &Mutex<Lobby>,
+    round: &Round,
+    on_update: &(dyn Fn(&[Standing]) + Send + Sync),
+) -> Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut receiver = Receiver::new(stream);
+    let name = match receiver.receive()? {
+        Some(Message::Join { name }) => name,
+        _ => {
+            let message = String::from("Expected a join message");
+            return send(&mut writer, &Message::Error { message })
+        }
+    };
+    send(&mut writer, &Message::Clash(round.game.clone()))?;
+    let mode = round.game.mode.as_str();
+
+    let standing = Standing {
+        name: name.clone(),
+        submission: None,
+        elapsed: 0,
+    };
+    let index = {
+        let mut lobby = Lobby::lock(lobby);
+        lobby.players.push((standing, writer.try_clone()?));
+        on_update(&lobby.broadcast(mode));
+        lobby.players.len() - 1
+    };
+
+    while let Some(message) = receiver.receive()? {
+        match message {
+            Message::Submit(submission) => {
+                let elapsed = round.started.elapsed();
+                match round.check_submission(&submission, elapsed) {
+                    Ok(()) => {
+                        let mut lobby = Lobby::lock(lobby);
+                        let standing = &mut lobby.players[index].0;
+                        standing.submission = Some(submission);
+                        standing.elapsed = elapsed.as_secs();
+                        on_update(&lobby.broadcast(mode));
+                    }
+                    Err(message) => send(&mut writer, &Message::Error { message })?,
+                }
+            }
+            other => {
+                let message = format!("Unexpected message from {}: {:?}", name, other);
+                send(&mut writer, &Message::Error { message })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A connection to the host of a LAN game.
+pub struct Player {
+    stream: TcpStream,
+    receiver: Receiver,
+}
+
+impl Player {
+    /// Joins the game at `addr` as `name`. Returns the connection and the
+    /// game sent by the host.
+    pub fn join(addr: impl ToSocketAddrs, name: &str) -> Result<(Self, Game)> {
+        let mut stream = TcpStream::connect(addr).context("Unable to connect to the host")?;
+        send(
+            &mut stream,
+            &Message::Join {
+                name: name.to_string(),
+            },
+        )?;
+        let mut receiver = Receiver::new(stream.try_clone()?);
+        match receiver.receive()? {
+            Some(Message::Clash(game)) => Ok((Player { stream, receiver }, game)),
+            Some(Message::Error { message }) => Err(anyhow!("The host refused to let us join: {}", message)),
+            _ => Err(anyhow!("The host did not send a clash")),
+        }
+    }
+
+    /// A second handle to the connection, for submitting while another
+    /// thread waits for messages.
+    pub fn sender(&self) -> Result<Sender> {
+        Ok(Sender {
+            stream: self.stream.try_clone()?,
+        })
+    }
+
+    /// Waits for the next message from the host.
+    pub fn receive(&mut self) -> Result<Option<Message>> {
+        self.receiver.receive()
+    }
+}
+
+/// Sends submissions to the host, see [Player::sender].
+pub struct Sender {
+    stream: TcpStream,
+}
+
+impl Sender {
+    pub fn submit(&mut self, submission: Submission) -> Result<()> {
+        send(&mut self.stream, &Message::Submit(submission))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_game() -> Game {
+        Game {
+            clash: std::fs::read_to_string("fixtures/puzzles/stub_and_solution_tester.json").unwrap(),
+            mode: String::from("shortest"),
+            minutes: 15,
+        }
+    }
+
+    #[test]
+    fn host_rejects_late_or_invalid_submissions() {
+        let round = Round {
+            game: sample_game(),
+            num_testcases: 8,
+            started: Instant::now(),
+        };
+        let submission = |passed, total| Submission {
+            passed,
+            total,
+            code_length: None,
+        };
+        assert!(round.check_submission(&submission(3, 8), Duration::from_secs(90)).is_ok());
+        assert!(round.check_submission(&submission(8, 8), Duration::from_secs(15 * 60 + 1)).is_err());
+        assert!(round.check_submission(&submission(9, 8), Duration::from_secs(90)).is_err());
+        assert!(round.check_submission(&submission(1, 1), Duration::from_secs(90)).is_err());
+    }
+
+    #[test]
+    fn players_get_the_clash_and_the_leaderboard() {
+        let game = sample_game();
+        let host = Host::bind("127.0.0.1:0", game.clone()).unwrap();
+        let addr = host.local_addr().unwrap();
+        std::thread::spawn(move || host.serve(|_| {}));
+
+        let (alice, received) = Player::join(addr, "alice").unwrap();
+        assert_eq!(received, game);
+        let (mut bob, _) = Player::join(addr, "bob").unwrap();
+
+        let submission = |passed, code_length| Submission {
+            passed,
+            total: 8,
+            code_length: Some(code_length),
+        };
+        alice.sender().unwrap().submit(submission(8, 120)).unwrap();
+        bob.sender().unwrap().submit(submission(8, 80)).unwrap();
+
+        // Skip the leaderboards sent before both submissions arrived
+        let standings = loop {
+            match bob.receive().unwrap() {
+                Some(Message::Leaderboard { standings })
+                    if standings.iter().all(|standing| standing.submission.is_some()) =>
+                {
+                    break standings
+                }
+                Some(_) => continue,
+                None => panic!("the host closed the connection"),
+            }
+        };
+        let names: Vec<&str> = standings.iter().map(|standing| standing.name.as_str()).collect();
+        assert_eq!(names, ["bob", "alice"]);
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+
+use clashlib::clash::Clash;
+use serde::Serialize;
+
+use super::formatter::scan_tags;
+
+/// Formatting tags of CodinGame statements that have to be balanced.
+const TAG_PAIRS: [(&str, &str); 3] = [("[[", "]]"), ("{{", "}}"), ("<<", ">>")];
+
+/// A problem found in a clash by [lint].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    /// Short name of the check, e.g. `"unbalanced-tag"`.
+    pub rule: &'static str,
+    pub location: Location,
+    pub message: String,
+}
+
+/// Where a [Diagnostic] was found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Location {
+    /// Position in one of the texts of the clash, e.g. the statement. Lines
+    /// and columns start from 1.
+    Text {
+        field: &'static str,
+        line: usize,
+        column: usize,
+    },
+    /// Testcase or validator, numbered from 1.
+    Testcase { index: usize },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Location::Text { field, line, column } => write!(f, "{}:{}:{}", field, line, column),
+            Location::Testcase { index } => write!(f, "testcase #{}", index),
+        }
+    }
+}
+
+/// Checks the formatting of the statement and descriptions of `clash` and
+/// the consistency of its testcases.
+pub fn lint(clash: &Clash) -> Vec<Diagnostic> {
+    let texts = [
+        ("statement", Some(clash.statement())),
+        ("input", Some(clash.input_description())),
+        ("output", Some(clash.output_description())),
+        ("constraints", clash.constraints()),
+    ];
+    let mut diagnostics = Vec::new();
+    for (field, text) in texts {
+        if let Some(text) = text {
+            lint_text(field, text, &mut diagnostics);
+        }
+    }
+    lint_testcases(clash, &mut diagnostics);
+    diagnostics
+}
+
+fn lint_text(field: &'static str, text: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let location = |offset: usize| {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Location::Text {
+            field,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    };
+
+    let obsolete_monospace = text.match_indices("```").map(|(offset, _)| {
+        let message = String::from("``` is obsolete formatting, use single backticks for monospace");
+        (offset, "obsolete-monospace", message)
+    });
+    let (_, unbalanced_tags) = scan_tags(text, &TAG_PAIRS);
+    let unbalanced = unbalanced_tags
+        .into_iter()
+        .map(|(offset, message)| (offset, "unbalanced-tag", message));
+    let mut problems: Vec<(usize, &str, String)> = obsolete_monospace.chain(unbalanced).collect();
+    problems.sort_by_key(|(offset, _, _)| *offset);
+    for (offset, rule, message) in problems {
+        diagnostics.push(Diagnostic {
+            rule,
+            location: location(offset),
+            message,
+        });
+    }
+}
+
+fn lint_testcases(clash: &Clash, diagnostics: &mut Vec<Diagnostic>) {
+    let testcases = clash.testcases();
+    for testcase in testcases {
+        let location = Location::Testcase {
+            index: testcase.index,
+        };
+        if testcase.test_out.lines().any(|line| line.ends_with(char::is_whitespace)) {
+            diagnostics.push(Diagnostic {
+                rule: "trailing-whitespace",
+                location: location.clone(),
+                message: String::from("Expected output has trailing whitespace on some lines"),
+            });
+        }
+        if testcase.test_out.ends_with('\n') {
+            diagnostics.push(Diagnostic {
+                rule: "trailing-whitespace",
+                location: location.clone(),
+                message: String::from("Expected output ends with an empty line"),
+            });
+        }
+
+        let earlier = testcases.iter().take_while(|other| other.index < testcase.index);
+        let mut same_input = earlier.filter(|other| other.test_in == testcase.test_in);
+        if let Some(other) = same_input.clone().find(|other| other.is_validator == testcase.is_validator) {
+            diagnostics.push(Diagnostic {
+                rule: "duplicate-testcase",
+                location: location.clone(),
+                message: format!("Same input as #{} {:?}", other.index, other.title),
+            });
+        } else if let Some(other) = same_input.find(|other| other.is_validator != testcase.is_validator) {
+            diagnostics.push(Diagnostic {
+                rule: "validator-equals-test",
+                location,
+                message: format!(
+                    "Validator and test #{} have the same input, so hardcoded solutions pass the validator",
+                    other.index
+                ),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use clashlib::clash::PublicHandle;
+
+    use super::*;
+
+    #[test]
+    fn reports_unbalanced_tags_and_testcase_problems() {
+        let clash = Clash::builder(PublicHandle::from_str("c0ffee").unwrap(), "Lint me")
+            .statement("Print [[n]] <<times [[m>>]]\nand {{k}\n```code```")
+            .constraints("1 << 3 ≤ [[n]]")
+            .testcase("A", "1", "2 \n3")
+            .validator("A", "1", "2")
+            .testcase("B", "2", "4\n")
+            .testcase("C", "2", "4")
+            .build();
+        let diagnostics = lint(&clash);
+        let found: Vec<(&str, String)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.rule, diagnostic.location.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("unbalanced-tag", String::from("statement:1:21")),
+                ("unbalanced-tag", String::from("statement:2:5")),
+                ("obsolete-monospace", String::from("statement:3:1")),
+                ("obsolete-monospace", String::from("statement:3:8")),
+                ("unbalanced-tag", String::from("constraints:1:3")),
+                ("trailing-whitespace", String::from("testcase #1")),
+                ("validator-equals-test", String::from("testcase #2")),
+                ("trailing-whitespace", String::from("testcase #3")),
+                ("duplicate-testcase", String::from("testcase #4")),
+            ]
+        );
+    }
+}
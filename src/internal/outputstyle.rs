@@ -1,9 +1,15 @@
 use ansi_term::{Color, Style};
 use clashlib::clash::{Clash, Testcase};
-use clashlib::solution::TestResult;
+use clashlib::solution::{BenchStats, TestResult, TestRun};
 
+use super::clash_filter::ClashFilter;
+use super::clash_index::IndexEntry;
 use super::formatter::show_whitespace;
+use super::history::{HistoryEntry, SolveCount, SolveStats};
+use super::lan::Standing;
 use super::lines_with_endings::LinesWithEndings;
+use super::lint::Diagnostic;
+use super::session::Round;
 use crate::internal::formatter::format_cg;
 
 pub struct OutputStyle {
@@ -117,25 +123,45 @@ impl OutputStyle {
 
     pub fn print_headers(&self, clash: &Clash) {
         println!("{}\n", self.title.paint(format!("=== {} ===", clash.title())));
-        println!("{}\n", self.link.paint(clash.codingame_link()));
+        println!("{}", self.link.paint(clash.codingame_link()));
+        println!("{}\n", self.dim_color.paint(clash_metadata(clash).join(" | ")));
     }
 
     pub fn print_statement(&self, clash: &Clash) {
-        println!("{}\n", format_cg(clash.statement(), self));
-        println!("{}\n{}\n", self.title.paint("Input:"), format_cg(clash.input_description(), self));
-        println!("{}\n{}\n", self.title.paint("Output:"), format_cg(clash.output_description(), self));
+        println!("{}", self.styled_statement(clash));
+    }
+
+    /// Statement, input and output descriptions, constraints and the example
+    /// testcase of a clash.
+    pub fn styled_statement(&self, clash: &Clash) -> String {
+        let mut text = format!("{}\n\n", format_cg(clash.statement(), self));
+        text.push_str(&format!(
+            "{}\n{}\n\n",
+            self.title.paint("Input:"),
+            format_cg(clash.input_description(), self)
+        ));
+        text.push_str(&format!(
+            "{}\n{}\n\n",
+            self.title.paint("Output:"),
+            format_cg(clash.output_description(), self)
+        ));
         if let Some(constraints) = clash.constraints() {
-            println!("{}\n{}\n", self.title.paint("Constraints:"), format_cg(constraints, self));
+            text.push_str(&format!(
+                "{}\n{}\n\n",
+                self.title.paint("Constraints:"),
+                format_cg(constraints, self)
+            ));
         }
 
         let example = clash.testcases().first().expect("example puzzle should have at least one testcase");
-        println!(
+        text.push_str(&format!(
             "{}\n{}\n{}\n{}",
             self.title.paint("Example:"),
             self.styled_testcase_input(example),
             self.title.paint("Expected output:"),
             self.styled_testcase_output(example),
-        );
+        ));
+        text
     }
 
     pub fn print_testcases(&self, clash: &Clash, selection: Vec<usize>) {
@@ -160,7 +186,7 @@ impl OutputStyle {
         self.print_testcases(clash, selection);
     }
 
-    fn print_diff(&self, testcase: &Testcase, stdout: &str) {
+    fn styled_diff(&self, testcase: &Testcase, stdout: &str) -> String {
         use dissimilar::Chunk::*;
         use itertools::EitherOrBoth::{Both, Left, Right};
         use itertools::Itertools;
@@ -171,18 +197,18 @@ impl OutputStyle {
         let diff_ws_green = &self.diff_green_whitespace;
 
         if stdout.is_empty() {
-            println!("{}", self.dim_color.paint("(no output)"));
-            return
+            return format!("{}\n", self.dim_color.paint("(no output)"))
         }
 
         let expected_lines = LinesWithEndings::from(&testcase.test_out);
         let actual_lines = LinesWithEndings::from(stdout);
 
+        let mut diff = String::new();
         let mut missing_lines = 0;
         for either_or_both in expected_lines.zip_longest(actual_lines) {
             match either_or_both {
                 Left(_) => missing_lines += 1,
-                Right(s) => print!("{}", show_whitespace(s, diff_red, diff_ws_red)),
+                Right(s) => diff.push_str(&show_whitespace(s, diff_red, diff_ws_red)),
                 Both(a, b) => {
                     let mut prev_deleted = false;
 
@@ -192,13 +218,17 @@ impl OutputStyle {
                                 let mut chars = text.chars();
                                 let first_char = chars.next().expect("diff chunk should not be empty");
                                 let rest = chars.as_str();
-                                print!("{}", show_whitespace(&first_char.to_string(), diff_red, diff_ws_red));
+                                diff.push_str(&show_whitespace(
+                                    &first_char.to_string(),
+                                    diff_red,
+                                    diff_ws_red,
+                                ));
                                 if !rest.is_empty() {
-                                    print!("{}", show_whitespace(rest, diff_green, diff_ws_green));
+                                    diff.push_str(&show_whitespace(rest, diff_green, diff_ws_green));
                                 }
                             }
-                            Equal(text) => print!("{}", show_whitespace(text, diff_green, diff_ws_green)),
-                            Insert(text) => print!("{}", show_whitespace(text, diff_red, diff_ws_red)),
+                            Equal(text) => diff.push_str(&show_whitespace(text, diff_green, diff_ws_green)),
+                            Insert(text) => diff.push_str(&show_whitespace(text, diff_red, diff_ws_red)),
                             Delete(_) => {}
                         }
 
@@ -209,62 +239,354 @@ impl OutputStyle {
         }
 
         if !stdout.ends_with('\n') {
-            println!()
+            diff.push('\n');
         }
 
         if missing_lines > 0 {
             let msg = format!("(expected {} more lines)", missing_lines);
-            println!("{}", self.dim_color.paint(msg));
+            diff.push_str(&format!("{}\n", self.dim_color.paint(msg)));
         }
+        diff
     }
 
-    pub fn print_result(&self, testcase: &Testcase, test_result: &TestResult) {
-        let title = self.styled_testcase_title(testcase);
-        match test_result {
-            TestResult::Success => {
-                println!("{} {}", self.success.paint("PASS"), title);
-            }
+    pub fn print_result(&self, testcase: &Testcase, test_run: &TestRun) {
+        print!("{}", self.styled_result(testcase, test_run));
+    }
+
+    /// Status line of a test run, followed by the details of the failure
+    /// (including a diff against the expected output) if it did not pass.
+    pub fn styled_result(&self, testcase: &Testcase, test_run: &TestRun) -> String {
+        let title =
+            format!("{} {}", self.styled_testcase_title(testcase), self.styled_measurements(test_run));
+        match &test_run.result {
+            TestResult::Success { .. } => format!("{} {}\n", self.success.paint("PASS"), title),
 
             TestResult::UnableToRun { error_msg } => {
-                println!("{} {}", self.failure.paint("ERROR"), title);
-                println!(" {}", self.stderr.paint(error_msg));
+                format!("{} {}\n {}\n", self.failure.paint("ERROR"), title, self.stderr.paint(error_msg))
             }
 
             TestResult::WrongOutput { stdout, stderr } => {
-                println!("{} {}", self.failure.paint("FAIL"), title);
-                self.print_failure(testcase, stdout, stderr);
+                let status = format!("{} {}\n", self.failure.paint("FAIL"), title);
+                status + &self.styled_failure(testcase, stdout, stderr)
             }
 
             TestResult::RuntimeError { stdout, stderr } => {
-                println!("{} {}", self.error.paint("ERROR"), title);
-                self.print_failure(testcase, stdout, stderr);
+                let status = format!("{} {}\n", self.error.paint("ERROR"), title);
+                status + &self.styled_failure(testcase, stdout, stderr)
             }
 
             TestResult::Timeout { stdout, stderr } => {
-                println!("{} {}", self.error.paint("TIMEOUT"), title);
-                self.print_failure(testcase, stdout, stderr);
+                let status = format!("{} {}\n", self.error.paint("TIMEOUT"), title);
+                status + &self.styled_failure(testcase, stdout, stderr)
+            }
+
+            TestResult::MemoryLimitExceeded { stdout, stderr } => {
+                let status = format!("{} {}\n", self.error.paint("MEMORY LIMIT"), title);
+                status + &self.styled_failure(testcase, stdout, stderr)
+            }
+
+            TestResult::OutputLimitExceeded { stdout, stderr } => {
+                let status = format!("{} {}\n", self.error.paint("OUTPUT LIMIT"), title);
+                status + &self.styled_failure(testcase, stdout, stderr)
             }
         }
     }
 
-    fn print_failure(&self, testcase: &Testcase, stdout: &str, stderr: &str) {
+    /// Wall time and peak memory of a run, e.g. `(12.3 ms, 4.5 MB)`
+    fn styled_measurements(&self, test_run: &TestRun) -> String {
+        let measurements = match test_run.peak_memory {
+            Some(bytes) => format!("({}, {})", format_duration(test_run.duration), format_memory(bytes)),
+            None => format!("({})", format_duration(test_run.duration)),
+        };
+        self.dim_color.paint(measurements).to_string()
+    }
+
+    pub fn print_bench(&self, testcase: &Testcase, stats: &BenchStats) {
+        let status = if stats.passed == stats.runs {
+            self.success.paint("PASS")
+        } else {
+            self.failure.paint("FAIL")
+        };
+        println!("{} {}", status, self.styled_testcase_title(testcase));
+        let peak_memory = stats.peak_memory.map(format_memory).unwrap_or_else(|| String::from("-"));
         println!(
-            "{}\n{}\n{}\n{}",
+            " min {} | median {} | max {} | peak memory {} | {}/{} runs passed",
+            format_duration(stats.min),
+            format_duration(stats.median),
+            format_duration(stats.max),
+            peak_memory,
+            stats.passed,
+            stats.runs,
+        );
+    }
+
+    /// One line per clash with its handle, modes (`F`astest, `S`hortest,
+    /// `R`everse), puzzle type, votes and title.
+    pub fn print_clash_table(&self, entries: &[IndexEntry]) {
+        if entries.is_empty() {
+            println!("No stored clash matches the filters");
+            return
+        }
+        let handle_width = entries.iter().map(|entry| entry.handle.to_string().len()).max().unwrap_or(0);
+        let header = format!(
+            "{:<handle_width$}  {:<5}  {:<7}  {:>8}  {}",
+            "HANDLE", "MODES", "TYPE", "VOTES", "TITLE"
+        );
+        println!("{}", self.bold.paint(header));
+        for entry in entries {
+            let modes: String = [(entry.fastest, 'F'), (entry.shortest, 'S'), (entry.reverse, 'R')]
+                .iter()
+                .map(|&(has_mode, letter)| if has_mode { letter } else { '-' })
+                .collect();
+            let votes = format!("+{}/-{}", entry.upvotes, entry.downvotes);
+            let topics = if entry.topics.is_empty() {
+                String::new()
+            } else {
+                self.dim_color.paint(format!(" [{}]", entry.topics.join(", "))).to_string()
+            };
+            println!(
+                "{:<handle_width$}  {:<5}  {:<7}  {:>8}  {}{}",
+                entry.handle.to_string(),
+                modes,
+                ClashFilter::type_name(entry.puzzle_type),
+                votes,
+                self.title.paint(&entry.title),
+                topics
+            );
+        }
+    }
+
+    /// One line per run: date, clash, language, mode, passed testcases and
+    /// how long running them took.
+    pub fn print_history(&self, entries: &[HistoryEntry]) {
+        if entries.is_empty() {
+            println!("No runs recorded yet");
+            return
+        }
+        for entry in entries {
+            let status = if entry.is_solved() {
+                self.success.paint("SOLVED")
+            } else {
+                self.failure.paint("FAILED")
+            };
+            println!(
+                "{} {} {} {} [{}] {}/{} {}",
+                self.dim_color.paint(format_timestamp(entry.timestamp)),
+                status,
+                entry.handle,
+                self.title.paint(&entry.language),
+                entry.mode.as_deref().unwrap_or("-"),
+                entry.passed,
+                entry.total,
+                self.dim_color.paint(format!("({})", format_duration(entry.duration))),
+            );
+        }
+    }
+
+    pub fn print_stats(&self, stats: &SolveStats) {
+        println!("{} runs", stats.runs);
+        println!("{}", format_solve_count("solved", &stats.overall));
+        for (title, groups) in [("By mode:", &stats.by_mode), ("By topic:", &stats.by_topic)] {
+            if groups.is_empty() {
+                continue
+            }
+            println!("\n{}", self.title.paint(title));
+            for (name, count) in groups {
+                println!("{}", format_solve_count(&format!("  {}", name), count));
+            }
+        }
+    }
+
+    /// Time left in a round, e.g. `12:34 left (shortest)`.
+    pub fn print_round_timer(&self, round: &Round, now: u64) {
+        let remaining = round.remaining(now);
+        let timer = if remaining == 0 {
+            self.failure.paint("Time is up!").to_string()
+        } else {
+            self.title.paint(format!("{} left", format_clock(remaining))).to_string()
+        };
+        println!("{} ({})\n", timer, round.mode);
+    }
+
+    /// One line per round of a session with its result.
+    pub fn print_rounds(&self, rounds: &[Round], now: u64) {
+        for (num, round) in (1..).zip(rounds) {
+            let result = match &round.result {
+                None if round.remaining(now) > 0 => {
+                    format!("playing, {} left", format_clock(round.remaining(now)))
+                }
+                None => String::from("time is up, not submitted"),
+                Some(result) => {
+                    let mut text = format!(
+                        "{}% ({}/{}) in {}",
+                        result.score(),
+                        result.passed,
+                        result.total,
+                        format_clock(result.elapsed)
+                    );
+                    if result.elapsed > round.time_limit {
+                        text.push_str(" (over time)");
+                    }
+                    if let Some(code_length) = result.code_length {
+                        text.push_str(&format!(", {} characters", code_length));
+                    }
+                    text
+                }
+            };
+            println!(
+                "Round {}: {} {:<8} {} runs, {}",
+                num,
+                round.handle,
+                round.mode,
+                round.runs.len(),
+                result
+            );
+        }
+    }
+
+    pub fn print_leaderboard(&self, standings: &[Standing], mode: &str) {
+        println!("{}", self.title.paint(format!("=== Leaderboard ({}) ===", mode)));
+        for (rank, standing) in (1..).zip(standings) {
+            let result = match &standing.submission {
+                None => self.dim_color.paint("no submission yet").to_string(),
+                Some(submission) => {
+                    let mut text = format!(
+                        "{}% ({}/{}) in {}",
+                        submission.score(),
+                        submission.passed,
+                        submission.total,
+                        format_clock(standing.elapsed)
+                    );
+                    if let Some(code_length) = submission.code_length {
+                        text.push_str(&format!(", {} characters", code_length));
+                    }
+                    text
+                }
+            };
+            println!("{:>2}. {:<16} {}", rank, standing.name, result);
+        }
+    }
+
+    pub fn print_diagnostics(&self, diagnostics: &[Diagnostic]) {
+        for diagnostic in diagnostics {
+            println!(
+                "{} {} {}",
+                self.error.paint(format!("{}:", diagnostic.location)),
+                diagnostic.message,
+                self.dim_color.paint(format!("[{}]", diagnostic.rule))
+            );
+        }
+    }
+
+    fn styled_failure(&self, testcase: &Testcase, stdout: &str, stderr: &str) -> String {
+        let mut text = format!(
+            "{}\n{}\n{}\n{}\n",
             self.secondary_title.paint("===== INPUT ======"),
             self.styled_testcase_input(testcase),
             self.secondary_title.paint("==== EXPECTED ===="),
             self.styled_testcase_output(testcase)
         );
 
-        println!("{}", &self.secondary_title.paint("===== STDOUT ====="));
-        self.print_diff(testcase, stdout);
+        text.push_str(&format!("{}\n", &self.secondary_title.paint("===== STDOUT =====")));
+        text.push_str(&self.styled_diff(testcase, stdout));
 
         if !stderr.is_empty() {
-            println!(
-                "{}\n{}",
+            text.push_str(&format!(
+                "{}\n{}\n",
                 self.secondary_title.paint("===== STDERR ====="),
                 self.stderr.paint(stderr.trim_end())
-            );
+            ));
         }
+        text
+    }
+}
+
+/// Author, votes, popularity, topics and moderation status of a clash, as
+/// short human readable items.
+pub fn clash_metadata(clash: &Clash) -> Vec<String> {
+    let mut items = Vec::new();
+    if let Some(author) = clash.author() {
+        items.push(format!("by {}", author));
+    }
+    items.push(format!("+{}/-{} votes", clash.upvotes(), clash.downvotes()));
+    items.push(format!("score {}", clash.score()));
+    items.push(format!("{} views", clash.views()));
+    items.push(format!("{} comments", clash.comment_count()));
+    if !clash.topics().is_empty() {
+        items.push(format!("topics: {}", clash.topics().join(", ")));
+    }
+    if let Some(status) = clash.status() {
+        items.push(format!("status: {}", status.to_lowercase()));
+    }
+    match clash.active_version() {
+        Some(active) if active != clash.last_version() => {
+            items.push(format!("version {} (latest {})", active, clash.last_version()))
+        }
+        _ => items.push(format!("version {}", clash.last_version())),
+    }
+    items
+}
+
+/// e.g. `solved: 3/4 clashes (75%)`
+fn format_solve_count(name: &str, count: &SolveCount) -> String {
+    format!(
+        "{}: {}/{} clashes ({:.0}%)",
+        name,
+        count.solved(),
+        count.attempted(),
+        count.solve_rate() * 100.0
+    )
+}
+
+/// Formats seconds as minutes and seconds, e.g. `12:05`
+fn format_clock(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Formats seconds since the Unix epoch as a UTC date, e.g. `2024-02-29 13:37`
+fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let minutes_of_day = timestamp % 86400 / 60;
+    // Converts days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes_of_day / 60,
+        minutes_of_day % 60
+    )
+}
+
+fn format_duration(duration: std::time::Duration) -> String {
+    if duration.as_secs() >= 1 {
+        format!("{:.2} s", duration.as_secs_f64())
+    } else {
+        format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+    }
+}
+
+fn format_memory(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_are_formatted_as_utc_dates() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(1709213820), "2024-02-29 13:37");
     }
 }
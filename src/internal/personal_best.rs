@@ -0,0 +1,69 @@
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use clashlib::clash::PublicHandle;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// Shortest code length of a passing solution for each clash and language.
+/// Stored as JSON: `{ "<handle>": { "<language>": <code length> } }`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PersonalBests {
+    bests: BTreeMap<String, BTreeMap<String, usize>>,
+}
+
+impl PersonalBests {
+    /// Reads personal bests from `path`. A missing file means there are no
+    /// personal bests yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn get(&self, handle: &PublicHandle, language: &str) -> Option<usize> {
+        self.bests.get(&handle.to_string())?.get(language).copied()
+    }
+
+    /// Records `code_length` if it is shorter than the previous best. Returns
+    /// true if it was recorded.
+    pub fn update(&mut self, handle: &PublicHandle, language: &str, code_length: usize) -> bool {
+        let best = self.bests.entry(handle.to_string()).or_default().entry(language.to_string());
+        match best {
+            Entry::Occupied(mut entry) if *entry.get() > code_length => {
+                entry.insert(code_length);
+                true
+            }
+            Entry::Occupied(_) => false,
+            Entry::Vacant(entry) => {
+                entry.insert(code_length);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn only_shorter_solutions_are_recorded() {
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let mut bests = PersonalBests::default();
+        assert!(bests.update(&handle, "py", 50));
+        assert!(!bests.update(&handle, "py", 60));
+        assert!(bests.update(&handle, "py", 40));
+        assert!(bests.update(&handle, "rb", 45));
+        assert_eq!(bests.get(&handle, "py"), Some(40));
+        assert_eq!(bests.get(&handle, "rb"), Some(45));
+    }
+}
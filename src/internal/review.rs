@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Result;
+use clashlib::clash::PublicHandle;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Solving a clash within this many seconds of picking it counts as a
+/// perfect recall.
+const FAST_SOLVE: u64 = 5 * 60;
+
+/// Solving a clash after this many seconds counts as a hard recall.
+const SLOW_SOLVE: u64 = 15 * 60;
+
+/// How well a clash went, on the 0-5 scale of SM-2. Grades below 3 are
+/// failures that reset the repetitions of the clash.
+pub type Grade = u8;
+
+/// Grade of a clash that was not solved.
+pub const FAILED: Grade = 1;
+
+/// Grade of a clash that was solved `seconds` after it was picked, or in an
+/// unknown amount of time.
+pub fn grade_solve(seconds: Option<u64>) -> Grade {
+    match seconds {
+        Some(seconds) if seconds <= FAST_SOLVE => 5,
+        Some(seconds) if seconds <= SLOW_SOLVE => 4,
+        Some(_) => 3,
+        None => 4,
+    }
+}
+
+/// Review state of a single clash.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewCard {
+    /// SM-2 easiness factor, at least 1.3. Harder clashes have a smaller
+    /// factor and their intervals grow slower.
+    pub easiness: f64,
+    /// Number of successful reviews in a row.
+    pub repetitions: u32,
+    pub interval_days: u64,
+    /// Seconds since the Unix epoch after which the clash should be reviewed.
+    pub due: u64,
+    /// Seconds since the Unix epoch of the last review.
+    pub last_review: u64,
+}
+
+impl Default for ReviewCard {
+    fn default() -> Self {
+        ReviewCard {
+            easiness: 2.5,
+            repetitions: 0,
+            interval_days: 0,
+            due: 0,
+            last_review: 0,
+        }
+    }
+}
+
+impl ReviewCard {
+    /// Schedules the next review with the SM-2 algorithm.
+    pub fn review(&mut self, grade: Grade, now: u64) {
+        let grade = grade.min(5);
+        if grade >= 3 {
+            self.interval_days = match self.repetitions {
+                0 => 1,
+                1 => 6,
+                _ => (self.interval_days as f64 * self.easiness).round() as u64,
+            };
+            self.repetitions += 1;
+        } else {
+            self.repetitions = 0;
+            self.interval_days = 1;
+        }
+        let penalty = f64::from(5 - grade);
+        self.easiness = (self.easiness + 0.1 - penalty * (0.08 + penalty * 0.02)).max(1.3);
+        self.due = now + self.interval_days * SECONDS_PER_DAY;
+        self.last_review = now;
+    }
+}
+
+/// When to review each clash. Stored as JSON: `{ "<handle>": <ReviewCard> }`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReviewSchedule {
+    cards: BTreeMap<String, ReviewCard>,
+}
+
+impl ReviewSchedule {
+    /// Reads the schedule from `path`. A missing file means no clash has been
+    /// reviewed yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    pub fn get(&self, handle: &PublicHandle) -> Option<&ReviewCard> {
+        self.cards.get(&handle.to_string())
+    }
+
+    /// Records a review of the clash with `handle`.
+    pub fn review(&mut self, handle: &PublicHandle, grade: Grade, now: u64) {
+        self.cards.entry(handle.to_string()).or_default().review(grade, now);
+    }
+
+    /// Picks the clash to review next among `candidates`: the one that has
+    /// been due the longest, or else a clash that was never reviewed, or else
+    /// the one that will be due soonest.
+    pub fn next_due<'a>(
+        &self,
+        candidates: impl IntoIterator<Item = &'a PublicHandle>,
+        now: u64,
+    ) -> Option<&'a PublicHandle> {
+        let mut new = None;
+        let mut earliest: Option<(&PublicHandle, u64)> = None;
+        for handle in candidates {
+            match self.get(handle) {
+                None => new = new.or(Some(handle)),
+                Some(card) if earliest.map_or(true, |(_, due)| card.due < due) => {
+                    earliest = Some((handle, card.due))
+                }
+                Some(_) => {}
+            }
+        }
+        match earliest {
+            Some((handle, due)) if due <= now => Some(handle),
+            _ => new.or(earliest.map(|(handle, _)| handle)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn failed_and_slow_clashes_come_back_sooner() {
+        let mut fast = ReviewCard::default();
+        let mut slow = ReviewCard::default();
+        for _ in 0..3 {
+            fast.review(grade_solve(Some(60)), 0);
+            slow.review(grade_solve(Some(30 * 60)), 0);
+        }
+        let mut failed = fast.clone();
+        failed.review(FAILED, 0);
+        assert_eq!(fast.interval_days, 16);
+        assert!(slow.interval_days < fast.interval_days);
+        assert_eq!((failed.repetitions, failed.interval_days), (0, 1));
+        assert!(failed.easiness < fast.easiness);
+    }
+
+    #[test]
+    fn due_clashes_are_reviewed_before_new_ones() {
+        let handles: Vec<PublicHandle> = ["abc1", "abc2", "abc3"]
+            .iter()
+            .map(|handle| PublicHandle::from_str(handle).unwrap())
+            .collect();
+        let mut schedule = ReviewSchedule::default();
+        schedule.review(&handles[0], 5, 0);
+        schedule.review(&handles[1], FAILED, 0);
+
+        let now = SECONDS_PER_DAY / 2;
+        assert_eq!(schedule.next_due(&handles, now), Some(&handles[2]));
+        let now = 2 * SECONDS_PER_DAY;
+        assert_eq!(schedule.next_due(&handles, now), Some(&handles[0]));
+        assert_eq!(schedule.next_due(&handles[..2], SECONDS_PER_DAY / 2), Some(&handles[0]));
+    }
+}
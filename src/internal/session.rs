@@ -0,0 +1,180 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use clashlib::clash::PublicHandle;
+use serde::{Deserialize, Serialize};
+
+use super::json_file;
+
+/// A run of a solution during a round.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundRun {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub passed: usize,
+    pub total: usize,
+}
+
+/// Final result of a round, recorded by `clash submit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub passed: usize,
+    pub total: usize,
+    /// Seconds from the start of the round until the submission, which can
+    /// be more than the time limit.
+    pub elapsed: u64,
+    /// Code length of the solution, for shortest mode.
+    pub code_length: Option<usize>,
+}
+
+impl RoundResult {
+    /// Percentage of passed testcases, like the score of a Clash of Code.
+    pub fn score(&self) -> usize {
+        (self.passed * 100).checked_div(self.total).unwrap_or(0)
+    }
+}
+
+/// One clash played against the clock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Round {
+    pub handle: PublicHandle,
+    /// Game mode of the round: fastest, shortest or reverse.
+    pub mode: String,
+    /// Seconds since the Unix epoch.
+    pub started: u64,
+    /// Seconds available to solve the clash.
+    pub time_limit: u64,
+    pub runs: Vec<RoundRun>,
+    /// `None` while the round is being played.
+    pub result: Option<RoundResult>,
+}
+
+impl Round {
+    /// Seconds left to play at `now`, zero once the time is up.
+    pub fn remaining(&self, now: u64) -> u64 {
+        (self.started + self.time_limit).saturating_sub(now)
+    }
+}
+
+/// Consecutive timed rounds, like a sequence of Clash of Code games. Stored
+/// as JSON in the data directory until the session is ended.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ClashSession {
+    pub rounds: Vec<Round>,
+}
+
+impl ClashSession {
+    /// Reads the session from `path`. A missing file means there is no
+    /// session going on.
+    pub fn load(path: &Path) -> Result<Self> {
+        json_file::load(path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        json_file::save(path, self)
+    }
+
+    /// The round that has not been submitted yet, if any.
+    pub fn current_round(&self) -> Option<&Round> {
+        self.rounds.last().filter(|round| round.result.is_none())
+    }
+
+    fn current_round_mut(&mut self) -> Option<&mut Round> {
+        self.rounds.last_mut().filter(|round| round.result.is_none())
+    }
+
+    /// The current round if it is played on the clash with `handle`.
+    pub fn round_of(&self, handle: &PublicHandle) -> Option<&Round> {
+        self.current_round().filter(|round| &round.handle == handle)
+    }
+
+    /// Handles of the clashes played in earlier rounds.
+    pub fn played(&self) -> impl Iterator<Item = &PublicHandle> {
+        self.rounds.iter().map(|round| &round.handle)
+    }
+
+    pub fn start_round(
+        &mut self,
+        handle: PublicHandle,
+        mode: String,
+        time_limit: u64,
+        now: u64,
+    ) -> Result<()> {
+        if let Some(round) = self.current_round() {
+            return Err(anyhow!(
+                "The round on clash {} is not over yet, use `coctus clash submit` first",
+                round.handle
+            ))
+        }
+        self.rounds.push(Round {
+            handle,
+            mode,
+            started: now,
+            time_limit,
+            runs: Vec::new(),
+            result: None,
+        });
+        Ok(())
+    }
+
+    /// Logs a run of the clash with `handle`. Returns false if no round is
+    /// played on that clash.
+    pub fn log_run(&mut self, handle: &PublicHandle, run: RoundRun) -> bool {
+        match self.current_round_mut().filter(|round| &round.handle == handle) {
+            Some(round) => {
+                round.runs.push(run);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the current round with the result of its last run.
+    pub fn submit(&mut self, code_length: Option<usize>, now: u64) -> Result<&Round> {
+        let round = self
+            .current_round_mut()
+            .context("No round is being played, use `coctus clash start`")?;
+        let last_run = round
+            .runs
+            .last()
+            .context("Nothing to submit yet, test your solution with `coctus run` first")?;
+        round.result = Some(RoundResult {
+            passed: last_run.passed,
+            total: last_run.total,
+            elapsed: now.saturating_sub(round.started),
+            code_length,
+        });
+        Ok(round)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn submit_uses_last_run_of_the_round() {
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        let mut session = ClashSession::default();
+        session.start_round(handle.clone(), String::from("fastest"), 900, 1000).unwrap();
+        assert!(session.start_round(handle.clone(), String::from("fastest"), 900, 1000).is_err());
+        assert!(session.submit(None, 1010).is_err());
+
+        let run = |passed| RoundRun {
+            timestamp: 1100,
+            passed,
+            total: 4,
+        };
+        assert!(session.log_run(&handle, run(1)));
+        assert!(session.log_run(&handle, run(3)));
+        assert!(!session.log_run(&PublicHandle::from_str("def456").unwrap(), run(4)));
+
+        let round = session.submit(None, 1200).unwrap();
+        let result = round.result.as_ref().unwrap();
+        assert_eq!((result.score(), result.elapsed), (75, 200));
+        assert!(session.current_round().is_none());
+        assert!(session.start_round(handle, String::from("reverse"), 900, 1300).is_ok());
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use clashlib::clash::PublicHandle;
+
+/// Stored solutions, one per clash and language:
+/// `<dir>/<handle>/<language>/<file name of the source file>`. The original
+/// file name is kept so that a solution can be built and run again with the
+/// commands configured for its language.
+pub struct SolutionArchive {
+    dir: PathBuf,
+}
+
+impl SolutionArchive {
+    pub fn new(dir: PathBuf) -> Self {
+        SolutionArchive { dir }
+    }
+
+    /// Stores a copy of `source_file` as the solution of the clash in
+    /// `language`, replacing the previous one. Returns the path of the copy.
+    pub fn save(&self, handle: &PublicHandle, language: &str, source_file: &Path) -> Result<PathBuf> {
+        let file_name =
+            source_file.file_name().with_context(|| format!("{:?} is not a file", source_file))?;
+        let source = std::fs::read(source_file)
+            .with_context(|| format!("Unable to read source file {:?}", source_file))?;
+        let language_dir = self.dir.join(handle.to_string()).join(language);
+        if language_dir.exists() {
+            std::fs::remove_dir_all(&language_dir)?;
+        }
+        std::fs::create_dir_all(&language_dir)?;
+        let path = language_dir.join(file_name);
+        std::fs::write(&path, source).with_context(|| format!("Unable to write {:?}", path))?;
+        Ok(path)
+    }
+
+    /// Path of the solution of the clash in `language`, if there is one.
+    pub fn get(&self, handle: &PublicHandle, language: &str) -> Option<PathBuf> {
+        let language_dir = self.dir.join(handle.to_string()).join(language);
+        let mut files = std::fs::read_dir(language_dir).ok()?.flatten().map(|entry| entry.path());
+        files.find(|path| path.is_file())
+    }
+
+    /// Languages that the clash was solved in, sorted by name.
+    pub fn languages(&self, handle: &PublicHandle) -> Result<Vec<String>> {
+        let clash_dir = self.dir.join(handle.to_string());
+        if !clash_dir.exists() {
+            return Ok(Vec::new())
+        }
+        let mut languages = subdirectories(&clash_dir)?;
+        languages.retain(|language| self.get(handle, language).is_some());
+        Ok(languages)
+    }
+
+    /// Clashes with at least one stored solution, sorted by handle.
+    pub fn handles(&self) -> Result<Vec<PublicHandle>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new())
+        }
+        let names = subdirectories(&self.dir)?;
+        Ok(names.iter().filter_map(|name| PublicHandle::from_str(name).ok()).collect())
+    }
+}
+
+fn subdirectories(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Unable to read {:?}", dir))? {
+        let path = entry?.path();
+        if let Some(name) = path.file_name().and_then(|name| name.to_str()).filter(|_| path.is_dir()) {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::TempDir;
+
+    #[test]
+    fn saving_replaces_the_previous_solution() {
+        let temp_dir = TempDir::new("archive-test");
+        let dir = temp_dir.path();
+        let archive = SolutionArchive::new(dir.join("solutions"));
+        let handle = PublicHandle::from_str("abc123").unwrap();
+        std::fs::write(dir.join("first.py"), "print(1)").unwrap();
+        std::fs::write(dir.join("second.py"), "print(2)").unwrap();
+
+        archive.save(&handle, "python", &dir.join("first.py")).unwrap();
+        archive.save(&handle, "python", &dir.join("second.py")).unwrap();
+        let saved = archive.get(&handle, "python").unwrap();
+        let handles = archive.handles().unwrap();
+        let languages = archive.languages(&handle).unwrap();
+
+        assert_eq!(saved.file_name().unwrap(), "second.py");
+        assert_eq!(std::fs::read_to_string(&saved).unwrap(), "print(2)");
+        assert_eq!(languages, ["python"]);
+        assert_eq!(handles, [handle]);
+    }
+}
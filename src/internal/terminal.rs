@@ -0,0 +1,187 @@
+use std::io::Write;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+
+/// A key pressed by the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Tab,
+    Enter,
+    Esc,
+    Char(char),
+    /// A letter pressed together with Ctrl, e.g. `Ctrl('c')`.
+    Ctrl(char),
+}
+
+/// Full-screen access to the terminal. While it exists the terminal is in raw
+/// mode and shows the alternate screen, both are restored when it is dropped.
+pub struct Terminal {
+    #[cfg(unix)]
+    original_mode: libc::termios,
+}
+
+#[cfg(unix)]
+impl Terminal {
+    pub fn enter() -> Result<Self> {
+        // SAFETY: tcgetattr and tcsetattr only access the termios struct we
+        // pass them.
+        let original_mode = unsafe {
+            let mut mode: libc::termios = std::mem::zeroed();
+            if libc::isatty(libc::STDIN_FILENO) == 0 || libc::tcgetattr(libc::STDIN_FILENO, &mut mode) != 0 {
+                return Err(anyhow!("STDIN is not a terminal"))
+            }
+            let original_mode = mode;
+            libc::cfmakeraw(&mut mode);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &mode) != 0 {
+                return Err(std::io::Error::last_os_error().into())
+            }
+            original_mode
+        };
+        // Alternate screen, hidden cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        std::io::stdout().flush()?;
+        Ok(Terminal { original_mode })
+    }
+
+    /// Number of columns and rows of the terminal.
+    pub fn size(&self) -> (usize, usize) {
+        // SAFETY: TIOCGWINSZ only writes to the winsize struct.
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        let ok = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if ok && size.ws_col > 0 && size.ws_row > 0 {
+            (size.ws_col as usize, size.ws_row as usize)
+        } else {
+            (80, 24)
+        }
+    }
+
+    /// Waits up to `timeout` for a key press.
+    pub fn read_key(&mut self, timeout: Duration) -> Result<Option<Key>> {
+        if !wait_for_input(timeout) {
+            return Ok(None)
+        }
+        let Some(byte) = read_byte() else { return Ok(None) };
+        let key = match byte {
+            b'\x1b' => self.read_escape_sequence(),
+            b'\t' => Key::Tab,
+            b'\r' | b'\n' => Key::Enter,
+            1..=26 => Key::Ctrl((b'a' + byte - 1) as char),
+            _ => Key::Char(read_utf8_char(byte)),
+        };
+        Ok(Some(key))
+    }
+
+    fn read_escape_sequence(&mut self) -> Key {
+        // A lone escape is not followed by anything (at least not right away)
+        let escape_timeout = Duration::from_millis(30);
+        if !wait_for_input(escape_timeout) {
+            return Key::Esc
+        }
+        match read_byte() {
+            Some(b'[') | Some(b'O') => {}
+            _ => return Key::Esc,
+        }
+        let mut sequence = Vec::new();
+        while let Some(byte) = wait_for_input(escape_timeout).then(read_byte).flatten() {
+            sequence.push(byte);
+            if byte.is_ascii_alphabetic() || byte == b'~' {
+                break
+            }
+        }
+        match sequence.as_slice() {
+            b"A" => Key::Up,
+            b"B" => Key::Down,
+            b"H" | b"1~" => Key::Home,
+            b"F" | b"4~" => Key::End,
+            b"5~" => Key::PageUp,
+            b"6~" => Key::PageDown,
+            _ => Key::Esc,
+        }
+    }
+
+    /// Replaces the contents of the screen with `lines`.
+    pub fn draw(&mut self, lines: &[String]) -> Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        write!(stdout, "\x1b[H{}", lines.join("\r\n"))?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+        // SAFETY: see Terminal::enter
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original_mode);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn wait_for_input(timeout: Duration) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout_ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+    // SAFETY: poll only accesses the single pollfd we pass it.
+    unsafe { libc::poll(&mut fd, 1, timeout_ms) > 0 }
+}
+
+/// Reads straight from the file descriptor, since the buffering of
+/// `std::io::Stdin` would hide pending input from `poll`.
+#[cfg(unix)]
+fn read_byte() -> Option<u8> {
+    let mut byte = 0u8;
+    // SAFETY: reads at most one byte into `byte`.
+    let n = unsafe { libc::read(libc::STDIN_FILENO, &mut byte as *mut u8 as *mut libc::c_void, 1) };
+    (n == 1).then_some(byte)
+}
+
+#[cfg(unix)]
+fn read_utf8_char(first_byte: u8) -> char {
+    let len = match first_byte {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    let mut bytes = vec![first_byte];
+    while bytes.len() < len {
+        match read_byte() {
+            Some(byte) => bytes.push(byte),
+            None => break,
+        }
+    }
+    std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()).unwrap_or('\u{fffd}')
+}
+
+#[cfg(not(unix))]
+impl Terminal {
+    pub fn enter() -> Result<Self> {
+        Err(anyhow!("The TUI is only supported on Unix-like systems"))
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (80, 24)
+    }
+
+    pub fn read_key(&mut self, _timeout: Duration) -> Result<Option<Key>> {
+        Ok(None)
+    }
+
+    pub fn draw(&mut self, _lines: &[String]) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Scratch directory for a test that is removed (with its contents) when
+/// dropped, even if the test fails.
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Creates an empty directory in the system temp dir. `name` only makes
+    /// leftovers easier to recognize, the directory is unique either way.
+    pub fn new(name: &str) -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let unique = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("coctus-{}-{}-{}", name, std::process::id(), unique));
+        if path.exists() {
+            std::fs::remove_dir_all(&path).expect("Stale temp dir should be removable");
+        }
+        std::fs::create_dir_all(&path).expect("Temp dir should be creatable");
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,301 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use clashlib::clash::Clash;
+use clashlib::solution::TestRun;
+
+use super::terminal::{Key, Terminal};
+use super::OutputStyle;
+
+/// How often the screen is redrawn when no key is pressed, so that resizing
+/// the terminal is picked up.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+const HELP: &str =
+    "↑/↓ select test  PgUp/PgDn scroll statement  u/d scroll details  Tab diff  r run  n next  q quit";
+
+/// Full-screen view of a clash: the statement on the left, the testcases on
+/// the right and the details of the selected testcase (or the diff of its last
+/// run) below them.
+pub struct Tui<'a> {
+    ostyle: &'a OutputStyle,
+    clash: Clash,
+    /// Result of the last run of each testcase.
+    runs: Vec<Option<TestRun>>,
+    selected: usize,
+    show_diff: bool,
+    statement_scroll: usize,
+    details_scroll: usize,
+    message: Option<String>,
+}
+
+impl<'a> Tui<'a> {
+    pub fn new(clash: Clash, ostyle: &'a OutputStyle) -> Self {
+        let runs = vec![None; clash.testcases().len()];
+        Tui {
+            ostyle,
+            clash,
+            runs,
+            selected: 0,
+            show_diff: false,
+            statement_scroll: 0,
+            details_scroll: 0,
+            message: None,
+        }
+    }
+
+    /// Takes over the terminal until the user quits. `run_tests` is called to
+    /// (re)run the solution against all testcases of a clash and `next_clash`
+    /// to switch to another clash.
+    pub fn run(
+        mut self,
+        mut run_tests: impl FnMut(&Clash) -> Result<Vec<TestRun>>,
+        mut next_clash: impl FnMut() -> Result<Clash>,
+    ) -> Result<()> {
+        let mut terminal = Terminal::enter()?;
+        loop {
+            let (width, height) = terminal.size();
+            terminal.draw(&self.render(width, height))?;
+
+            let Some(key) = terminal.read_key(REDRAW_INTERVAL)? else {
+                continue
+            };
+            self.message = None;
+            let body_height = height.saturating_sub(2).max(1);
+            match key {
+                Key::Char('q') | Key::Esc | Key::Ctrl('c') => break,
+                Key::Up | Key::Char('k') => self.select(self.selected.saturating_sub(1)),
+                Key::Down | Key::Char('j') => self.select(self.selected + 1),
+                Key::Home => self.select(0),
+                Key::End => self.select(self.runs.len().saturating_sub(1)),
+                Key::PageUp | Key::Char('K') => {
+                    self.statement_scroll = self.statement_scroll.saturating_sub(body_height / 2)
+                }
+                Key::PageDown | Key::Char('J') => self.statement_scroll += body_height / 2,
+                Key::Char('u') => self.details_scroll = self.details_scroll.saturating_sub(body_height / 4),
+                Key::Char('d') => self.details_scroll += body_height / 4,
+                Key::Tab | Key::Enter => {
+                    self.show_diff = !self.show_diff;
+                    self.details_scroll = 0;
+                }
+                Key::Char('r') => {
+                    self.message = Some(String::from("Running tests..."));
+                    terminal.draw(&self.render(width, height))?;
+                    self.message = match run_tests(&self.clash) {
+                        Ok(runs) => Some(self.record_runs(runs)),
+                        Err(error) => Some(format!("Error: {:#}", error)),
+                    };
+                }
+                Key::Char('n') => match next_clash() {
+                    Ok(clash) => self = Tui::new(clash, self.ostyle),
+                    Err(error) => self.message = Some(format!("Error: {:#}", error)),
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.runs.len().saturating_sub(1));
+        self.details_scroll = 0;
+    }
+
+    /// Stores the results of a run, selects the first testcase that did not
+    /// pass and returns a summary.
+    fn record_runs(&mut self, runs: Vec<TestRun>) -> String {
+        self.runs = runs
+            .into_iter()
+            .map(Some)
+            .chain(std::iter::repeat(None))
+            .take(self.runs.len())
+            .collect();
+        let num_passed = self.runs.iter().flatten().filter(|run| run.is_success()).count();
+        if let Some(failed) = self.runs.iter().position(|run| !run.as_ref().is_some_and(TestRun::is_success))
+        {
+            self.select(failed);
+        }
+        self.show_diff = true;
+        format!("{}/{} testcases passed", num_passed, self.runs.len())
+    }
+
+    /// Lines of the screen, each exactly `width` columns wide.
+    fn render(&mut self, width: usize, height: usize) -> Vec<String> {
+        let ostyle = self.ostyle;
+        let body_height = height.saturating_sub(2).max(1);
+        let left_width = (width * 3 / 5).max(1);
+        let right_width = width.saturating_sub(left_width + 1).max(1);
+
+        let header = format!(
+            "{} {}",
+            ostyle.title.paint(format!("=== {} ===", self.clash.title())),
+            ostyle.link.paint(self.clash.codingame_link())
+        );
+
+        let statement = if self.clash.is_reverse_only() {
+            ostyle.title.paint("REVERSE!").to_string()
+        } else {
+            ostyle.styled_statement(&self.clash)
+        };
+        let statement_lines = wrap_ansi(&statement, left_width);
+        self.statement_scroll = self.statement_scroll.min(statement_lines.len().saturating_sub(1));
+
+        let mut right_lines = self.testcase_list(body_height / 3);
+        right_lines.push(ostyle.dim_color.paint("─".repeat(right_width)).to_string());
+        let details = wrap_ansi(&self.details(), right_width);
+        self.details_scroll = self.details_scroll.min(details.len().saturating_sub(1));
+        right_lines.extend(details.into_iter().skip(self.details_scroll));
+
+        let separator = ostyle.dim_color.paint("│").to_string();
+        let mut lines = vec![fit_ansi(&header, width)];
+        for row in 0..body_height {
+            let left = statement_lines.get(self.statement_scroll + row).map_or("", String::as_str);
+            let right = right_lines.get(row).map_or("", String::as_str);
+            lines.push(format!(
+                "{}{}{}",
+                fit_ansi(left, left_width),
+                separator,
+                fit_ansi(right, right_width)
+            ));
+        }
+        let status = self.message.as_deref().unwrap_or(HELP);
+        lines.push(fit_ansi(&ostyle.dim_color.paint(status).to_string(), width));
+        lines.truncate(height);
+        lines
+    }
+
+    /// One line per testcase with the result of its last run, scrolled so
+    /// that the selected testcase is visible.
+    fn testcase_list(&self, max_lines: usize) -> Vec<String> {
+        let max_lines = max_lines.max(1);
+        let first = (self.selected + 1).saturating_sub(max_lines);
+        let testcases = self.clash.testcases().iter().zip(&self.runs).enumerate();
+        testcases
+            .skip(first)
+            .take(max_lines)
+            .map(|(idx, (testcase, run))| {
+                let cursor = if idx == self.selected { ">" } else { " " };
+                let status = match run {
+                    None => self.ostyle.dim_color.paint(" -- "),
+                    Some(run) if run.is_success() => self.ostyle.success.paint("PASS"),
+                    Some(_) => self.ostyle.failure.paint("FAIL"),
+                };
+                format!("{} {} {}", cursor, status, self.ostyle.styled_testcase_title(testcase))
+            })
+            .collect()
+    }
+
+    /// Input and expected output of the selected testcase, or the result of
+    /// its last run when showing the diff.
+    fn details(&self) -> String {
+        let ostyle = self.ostyle;
+        let Some(testcase) = self.clash.testcases().get(self.selected) else {
+            return String::new()
+        };
+        match &self.runs[self.selected] {
+            Some(run) if self.show_diff => ostyle.styled_result(testcase, run),
+            _ => format!(
+                "{}\n{}\n{}\n{}",
+                ostyle.secondary_title.paint("===== INPUT ======"),
+                ostyle.styled_testcase_input(testcase),
+                ostyle.secondary_title.paint("==== EXPECTED ===="),
+                ostyle.styled_testcase_output(testcase),
+            ),
+        }
+    }
+}
+
+/// Splits `text` into lines of at most `width` columns, wrapping long lines.
+/// ANSI escape sequences do not take up any columns, and the ones in effect at
+/// the end of a line are repeated at the start of the next one so that every
+/// line can be drawn on its own.
+fn wrap_ansi(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut columns = 0;
+    // Escape sequences since the last reset
+    let mut active = String::new();
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => {
+                let mut sequence = String::from(c);
+                while let Some(next) = chars.next_if(|next| !next.is_ascii_alphabetic()) {
+                    sequence.push(next);
+                }
+                sequence.extend(chars.next());
+                if sequence == "\x1b[0m" {
+                    active.clear();
+                } else {
+                    active.push_str(&sequence);
+                }
+                line.push_str(&sequence);
+            }
+            '\n' => {
+                line.push_str("\x1b[0m");
+                lines.push(std::mem::replace(&mut line, active.clone()));
+                columns = 0;
+            }
+            '\r' => {}
+            _ => {
+                if columns == width {
+                    line.push_str("\x1b[0m");
+                    lines.push(std::mem::replace(&mut line, active.clone()));
+                    columns = 0;
+                }
+                if c == '\t' {
+                    line.push(' ');
+                } else {
+                    line.push(c);
+                }
+                columns += 1;
+            }
+        }
+    }
+    line.push_str("\x1b[0m");
+    lines.push(line);
+    lines
+}
+
+/// Cuts `line` (which may contain ANSI escape sequences) to `width` columns,
+/// or pads it with spaces if it is shorter.
+fn fit_ansi(line: &str, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut columns = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            fitted.push(c);
+            while let Some(next) = chars.next_if(|next| !next.is_ascii_alphabetic()) {
+                fitted.push(next);
+            }
+            fitted.extend(chars.next());
+        } else if columns < width {
+            fitted.push(c);
+            columns += 1;
+        }
+    }
+    fitted.push_str("\x1b[0m");
+    fitted.push_str(&" ".repeat(width - columns));
+    fitted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_ansi_repeats_active_style() {
+        let text = "\x1b[33mabcdef\x1b[0m\ngh";
+        let lines = wrap_ansi(text, 4);
+        assert_eq!(lines, ["\x1b[33mabcd\x1b[0m", "\x1b[33mef\x1b[0m\x1b[0m", "gh\x1b[0m"]);
+    }
+
+    #[test]
+    fn fit_ansi_ignores_escape_sequences() {
+        assert_eq!(fit_ansi("\x1b[1mab\x1b[0m", 4), "\x1b[1mab\x1b[0m\x1b[0m  ");
+        assert_eq!(fit_ansi("abcdef", 3), "abc\x1b[0m");
+    }
+}
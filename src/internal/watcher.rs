@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+/// Polls files for modifications. Directories are watched recursively
+/// (ignoring hidden files).
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    snapshot: Vec<(PathBuf, SystemTime)>,
+}
+
+impl Watcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        let snapshot = snapshot(&paths);
+        Watcher { paths, snapshot }
+    }
+
+    /// Blocks until any of the watched files is modified, created or removed.
+    pub fn wait_for_change(&mut self) {
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let current = snapshot(&self.paths);
+            if current != self.snapshot {
+                // Editors often save in several steps, wait until the files
+                // stop changing before reporting the change.
+                self.snapshot = current;
+                loop {
+                    std::thread::sleep(POLL_INTERVAL);
+                    let current = snapshot(&self.paths);
+                    if current == self.snapshot {
+                        return
+                    }
+                    self.snapshot = current;
+                }
+            }
+        }
+    }
+}
+
+fn snapshot(paths: &[PathBuf]) -> Vec<(PathBuf, SystemTime)> {
+    let mut files = Vec::new();
+    for path in paths {
+        collect_modification_times(path.clone(), &mut files);
+    }
+    files.sort();
+    files
+}
+
+fn collect_modification_times(path: PathBuf, files: &mut Vec<(PathBuf, SystemTime)>) {
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        return
+    };
+    if metadata.is_dir() {
+        let Ok(entries) = std::fs::read_dir(&path) else {
+            return
+        };
+        for entry in entries.flatten() {
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                collect_modification_times(entry.path(), files);
+            }
+        }
+    } else if let Ok(modified) = metadata.modified() {
+        files.push((path, modified));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::test_helper::TempDir;
+
+    #[test]
+    fn snapshot_changes_when_file_is_added() {
+        let temp_dir = TempDir::new("watcher-test");
+        let dir = temp_dir.path().to_path_buf();
+        let file = dir.join("sol.py");
+        std::fs::write(&file, "print(1)").unwrap();
+
+        let before = snapshot(std::slice::from_ref(&dir));
+        assert_eq!(before.len(), 1);
+        std::fs::write(dir.join("helper.py"), "x = 1").unwrap();
+        let after = snapshot(std::slice::from_ref(&dir));
+        assert_ne!(before, after);
+    }
+}
@@ -1,18 +1,33 @@
 mod internal;
 
-use std::io::Read;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str::FromStr;
+use std::time::{Instant, SystemTime};
 
 use anyhow::{anyhow, Context, Result};
+use clap::parser::ValueSource;
 use clap::ArgMatches;
 use clashlib::clash::{Clash, PublicHandle, Testcase};
-use clashlib::stub::StubConfig;
+use clashlib::solution::{Checker, ReportFormat, SuiteReport, TestResult, TestRun};
+use clashlib::stub::{StubConfig, StubParseError};
 use clashlib::{solution, stub};
 use directories::ProjectDirs;
-use internal::OutputStyle;
+use internal::{
+    clash_metadata, grade_solve, lint, unix_timestamp, ClashFilter, ClashIndex, ClashProject, ClashSession,
+    Config, CustomTests, Game, HistoryEntry, Host, IndexEntry, LanguageConfig, Message, OutputStyle,
+    PersonalBests, Player, ReviewSchedule, RoundRun, SolutionArchive, SolveHistory, Submission, Tui, Watcher,
+    FAILED,
+};
+use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
+use rand::SeedableRng;
+
+/// TCP port of LAN games when none is given.
+const DEFAULT_PORT: u16 = 4242;
 
 fn command_from_argument(cmd_arg: Option<&String>) -> Result<Option<Command>> {
     let cmd = match cmd_arg {
@@ -33,6 +48,210 @@ fn command_from_argument(cmd_arg: Option<&String>) -> Result<Option<Command>> {
     }
 }
 
+/// Runs the --build-command (if any) in `work_dir` (or the current directory)
+/// and fails if it does not succeed.
+fn build_solution(args: &ArgMatches, lang: &LanguageConfig, work_dir: Option<&Path>) -> Result<()> {
+    let build_command_arg = args.get_one::<String>("build-command").or(lang.build_command.as_ref());
+    if let Some(mut build_command) = command_from_argument(build_command_arg)? {
+        if let Some(dir) = work_dir {
+            build_command.current_dir(dir);
+        }
+        let build = build_command.output()?;
+
+        // The output is part of the error rather than printed, so that it can
+        // also be shown in the TUI.
+        if !build.status.success() {
+            let mut msg = String::from("Build failed");
+            if !build.stderr.is_empty() {
+                msg.push_str(&format!("\nBuild command STDERR:\n{}", String::from_utf8(build.stderr)?));
+            }
+            if !build.stdout.is_empty() {
+                msg.push_str(&format!("\nBuild command STDOUT:\n{}", String::from_utf8(build.stdout)?));
+            }
+            return Err(anyhow!(msg))
+        }
+    }
+    Ok(())
+}
+
+/// Returns the command given with --command or the one from the language
+/// config.
+fn run_command_from_args(args: &ArgMatches, lang: &LanguageConfig) -> Result<Command> {
+    let run_command_arg = args.get_one::<String>("command").or(lang.command.as_ref());
+    command_from_argument(run_command_arg)?
+        .context("No --command given (use --lang to pick one from coctus.toml instead)")
+}
+
+/// Directory that is removed together with its contents when dropped.
+struct ScratchDir(PathBuf);
+
+impl std::ops::Deref for ScratchDir {
+    type Target = Path;
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn checker_from_args(args: &ArgMatches) -> Result<Box<dyn Checker>> {
+    solution::parse_checker(args.get_one::<String>("checker").map_or("exact", |checker| checker))
+}
+
+fn ranges_from_args(args: &ArgMatches) -> HashMap<String, RangeInclusive<i64>> {
+    args.get_many::<(String, RangeInclusive<i64>)>("range")
+        .map(|ranges| ranges.cloned().collect())
+        .unwrap_or_default()
+}
+
+/// The --seed, or else a random seed that is printed so that the run can be
+/// reproduced.
+fn seed_from_args(args: &ArgMatches) -> u64 {
+    match args.get_one::<u64>("seed") {
+        Some(seed) => *seed,
+        None => {
+            let seed = rand::random();
+            eprintln!("Seed: {}", seed);
+            seed
+        }
+    }
+}
+
+fn timeout_from_args(args: &ArgMatches, lang: &LanguageConfig) -> Result<std::time::Duration> {
+    // The timeout from the language config overrides the default value of
+    // --timeout, but not one given on the command line.
+    let secs = match (args.value_source("timeout"), lang.timeout) {
+        (Some(ValueSource::CommandLine), _) | (_, None) => *args.get_one::<f64>("timeout").unwrap_or(&5.0),
+        (_, Some(secs)) => secs,
+    };
+    if secs.is_nan() {
+        Err(anyhow!("Timeout can't be NaN"))
+    } else if secs < 0.0 {
+        Err(anyhow!("Timeout can't be negative (use 0 for no timeout)"))
+    } else if secs == 0.0 {
+        Ok(std::time::Duration::MAX)
+    } else {
+        Ok(std::time::Duration::from_micros((secs * 1e6) as u64))
+    }
+}
+
+/// Applies the --testcases, --only-tests and --only-validators arguments.
+fn select_testcases<'a>(all_testcases: &'a [Testcase], args: &ArgMatches) -> Vec<&'a Testcase> {
+    let testcases: Vec<&Testcase> = if let Some(testcase_indices) = args.get_many::<u64>("testcases") {
+        testcase_indices.map(|idx| &all_testcases[(idx - 1) as usize]).collect()
+    } else {
+        all_testcases.iter().collect()
+    };
+    testcases.into_iter().filter(|testcase| is_selected_kind(testcase, args)).collect()
+}
+
+/// The game mode of a clash that can only be played in one mode.
+fn only_mode(clash: &Clash) -> Option<String> {
+    let modes = [
+        (clash.is_fastest(), "fastest"),
+        (clash.is_shortest(), "shortest"),
+        (clash.is_reverse(), "reverse"),
+    ];
+    let mut available = modes.iter().filter(|(available, _)| *available);
+    match (available.next(), available.next()) {
+        (Some((_, mode)), None) => Some(mode.to_string()),
+        _ => None,
+    }
+}
+
+/// Builds the solution and runs it against every testcase of `clash`.
+fn run_all_testcases(args: &ArgMatches, lang: &LanguageConfig, clash: &Clash) -> Result<Vec<TestRun>> {
+    let timeout = timeout_from_args(args, lang)?;
+    let checker = checker_from_args(args)?;
+    let limits = solution::ResourceLimits::default();
+    build_solution(args, lang, None)?;
+    let mut run_command = run_command_from_args(args, lang)?;
+    let suite_run = solution::lazy_run(clash.testcases(), &mut run_command, &timeout, &limits, &*checker);
+    Ok(suite_run.into_iter().map(|(_, test_run)| test_run).collect())
+}
+
+/// A random game mode among the ones available in `clash`.
+fn random_mode(clash: &Clash) -> String {
+    let modes = [
+        (clash.is_fastest(), "fastest"),
+        (clash.is_shortest(), "shortest"),
+        (clash.is_reverse(), "reverse"),
+    ];
+    let available = modes.iter().filter(|(available, _)| *available).map(|(_, mode)| mode);
+    available.choose(&mut rand::thread_rng()).copied().unwrap_or("fastest").to_string()
+}
+
+fn print_code_length(code_length: usize, previous_best: Option<usize>, passed: bool) {
+    match previous_best {
+        Some(best) if passed && code_length < best => {
+            println!("Code length: {code_length} characters (new personal best, previous: {best})")
+        }
+        Some(best) => println!("Code length: {code_length} characters (personal best: {best})"),
+        None if passed => println!("Code length: {code_length} characters (new personal best)"),
+        None => println!("Code length: {code_length} characters"),
+    }
+}
+
+/// Applies the --only-tests and --only-validators flags.
+fn is_selected_kind(testcase: &Testcase, args: &ArgMatches) -> bool {
+    if args.get_flag("only-tests") {
+        !testcase.is_validator
+    } else if args.get_flag("only-validators") {
+        testcase.is_validator
+    } else {
+        true
+    }
+}
+
+/// Applies the --no-custom and --only-custom flags. Custom testcases are
+/// numbered after the `num_clash_testcases` testcases of the clash.
+fn is_selected_origin(testcase: &Testcase, num_clash_testcases: usize, args: &ArgMatches) -> bool {
+    let is_custom = testcase.index > num_clash_testcases;
+    if args.get_flag("no-custom") {
+        !is_custom
+    } else if args.get_flag("only-custom") {
+        is_custom
+    } else {
+        true
+    }
+}
+
+/// Parses a `--range` argument like `N=1..100` or `N=5`.
+fn parse_variable_range(arg: &str) -> Result<(String, RangeInclusive<i64>)> {
+    let (ident, range) = arg.split_once('=').context("expected VARIABLE=LOW..HIGH")?;
+    let (low, high) = match range.split_once("..") {
+        Some((low, high)) => (low, high.trim_start_matches('=')),
+        None => (range, range),
+    };
+    let low: i64 = low.trim().parse().with_context(|| format!("invalid lower bound {:?}", low))?;
+    let high: i64 = high.trim().parse().with_context(|| format!("invalid upper bound {:?}", high))?;
+    if low > high {
+        return Err(anyhow!("the range {}..{} is empty", low, high))
+    }
+    Ok((ident.trim().to_string(), low..=high))
+}
+
+/// Filters on the metadata of stored clashes, shared by `list` and `next`.
+fn clash_filter_args() -> Vec<clap::Arg> {
+    use clap::{arg, value_parser};
+
+    vec![
+        arg!(--"type" <TYPE> "only consider clashes of code or classic puzzles")
+            .value_parser(["clash", "classic"]),
+        arg!(--"min-upvotes" <N> "only consider clashes with at least N upvotes")
+            .value_parser(value_parser!(i32)),
+        arg!(--"max-downvotes" <N> "only consider clashes with at most N downvotes")
+            .value_parser(value_parser!(i32)),
+        arg!(--"topic" <TOPIC> "only consider clashes with this topic"),
+        arg!(--"author" <NICKNAME> "only consider clashes written by NICKNAME"),
+        arg!(--"status" <STATUS> "only consider clashes whose latest status is STATUS (e.g. accepted)"),
+    ]
+}
+
 fn cli() -> clap::Command {
     use clap::{arg, value_parser, Command};
 
@@ -57,6 +276,25 @@ fn cli() -> clap::Command {
                 )
                 .arg(arg!(-'r' --"reverse" "print the clash in reverse mode"))
         )
+        .subcommand(
+            Command::new("list")
+                .visible_alias("search")
+                .about("List locally stored clashes")
+                .arg(arg!([QUERY] "only list clashes whose title or statement contain all words of QUERY"))
+                .arg(arg!(-'r' --"reverse" "only list clashes that have reverse mode"))
+                .arg(arg!(-'s' --"shortest" "only list clashes that have shortest mode"))
+                .arg(arg!(-'f' --"fastest" "only list clashes that have fastest mode"))
+                .args(clash_filter_args())
+                .arg(
+                    arg!(--"format" <FORMAT> "how to print the clashes")
+                        .value_parser(["table", "json"])
+                        .default_value("table")
+                )
+                .after_help(
+                    "The filters can be combined, a clash has to match all of them to be listed.\
+                    \nThe search is case insensitive and needs to read every clash that matches the other filters."
+                )
+        )
         .subcommand(
             Command::new("next")
                 .about("Select next clash")
@@ -68,24 +306,82 @@ fn cli() -> clap::Command {
                 .arg(arg!(-'r' --"reverse" "pick a random clash that has reverse mode"))
                 .arg(arg!(-'s' --"shortest" "pick a random clash that has shortest mode"))
                 .arg(arg!(-'f' --"fastest" "pick a random clash that has fastest mode"))
+                .arg(arg!(-'u' --"unsolved" "pick a random clash that you have not solved yet"))
+                .arg(
+                    arg!(--"review" "pick the clash that is due for review (spaced repetition)")
+                        .conflicts_with("unsolved")
+                )
+                .args(clash_filter_args())
                 .after_help(
                     "Pick a random clash from locally stored clashes when PUBLIC_HANDLE is not given.\
                     \nIf instead flags modes are supplied, it will look for a clash that has at least all of those modes available.\
-                    \nFor example: coctus next --fastest --shortest will return a clash that has BOTH fastest and shortest as options."
+                    \nFor example: coctus next --fastest --shortest will return a clash that has BOTH fastest and shortest as options.\
+                    \nThe other filters narrow the choice down in the same way, e.g. coctus next --topic strings --min-upvotes 5.\
+                    \n\
+                    \nWith --review, clashes are scheduled based on your past runs: a clash comes back after a day,\
+                    \nthen after increasingly long intervals. Clashes that you failed or solved slowly come back sooner.\
+                    \nWhen no clash is due, a clash that was never reviewed is picked."
                 )
         )
         .subcommand(
             Command::new("run")
                 .about("Test a solution against current clash")
                 .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
-                .arg(arg!(--"command" <COMMAND> "command that executes the solution").required(true))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution"))
+                .arg(arg!(--"lang" <LANGUAGE> "use the build command, command, timeout and source file configured for LANGUAGE"))
                 .arg(
                     arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
                         .value_parser(value_parser!(f64))
                         .default_value("5")
                 )
+                .arg(
+                    arg!(--"checker" <CHECKER> "how to compare the output with the expected output (see below)")
+                        .default_value("exact")
+                )
+                .arg(
+                    arg!(--"memory-limit" <MEGABYTES> "make allocations of the solution fail beyond this memory (Unix only)")
+                        .value_parser(value_parser!(u64))
+                )
+                .arg(
+                    arg!(--"cpu-limit" <SECONDS> "how many seconds of CPU time each testcase may use (Unix only)")
+                        .value_parser(value_parser!(u64).range(1..))
+                )
+                .arg(
+                    arg!(--"output-limit" <KILOBYTES> "stop the solution if it prints more than this to STDOUT")
+                        .value_parser(value_parser!(u64))
+                )
+                .arg(
+                    arg!(--"format" <FORMAT> "print a machine readable report instead of the normal output")
+                        .value_parser(["json", "junit", "tap"])
+                )
+                .arg(
+                    arg!(--"source" <FILE> "source file of the solution, used to count its code length")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"watch" <PATH> ... "re-run the build command and the tests whenever PATH changes")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with("auto-advance")
+                )
                 .arg(arg!(--"auto-advance" "automatically move on to next clash if all testcases pass"))
+                .arg(
+                    arg!(--"saved" <LANGUAGE> "run the solution saved for LANGUAGE instead (see `coctus solutions`)")
+                        .conflicts_with_all(["lang", "source", "watch"])
+                )
+                .arg(
+                    arg!(--"mode" <MODE> "game mode the solution is written for, recorded in the history")
+                        .value_parser(["fastest", "shortest", "reverse"])
+                )
                 .arg(arg!(--"ignore-failures" "run all tests despite failures"))
+                .arg(arg!(--"only-tests" "skip validators"))
+                .arg(arg!(--"only-validators" "skip tests (run only validators)").conflicts_with("only-tests"))
+                .arg(arg!(--"no-custom" "skip the testcases added with `coctus addtest`"))
+                .arg(arg!(--"only-custom" "only run the testcases added with `coctus addtest`").conflicts_with("no-custom"))
+                .arg(
+                    arg!(-'j' --"jobs" <N> "how many testcases to run in parallel")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("1")
+                )
                 .arg(
                     arg!(--"testcases" <TESTCASE_INDICES> "indices of the testcases to run (separated by commas)")
                         .value_parser(value_parser!(u64).range(1..99))
@@ -104,13 +400,145 @@ fn cli() -> clap::Command {
                 )
                 .after_help(
                     "If a --build-command is specified, it will be executed once before running any of the testcases. \
-                    The --command is required and will be executed once per testcase.\
+                    The --command will be executed once per testcase.\
+                    \nInstead of --build-command, --command, --timeout and --source you can use --lang LANGUAGE \
+                    to read them from a coctus.toml file (in the config directory or the current directory).\
+                    \nThe --checker can be one of: exact (the default, like CodinGame), tokens (ignore whitespace), \
+                    float[:EPSILON] (numbers may differ by EPSILON), unordered-lines, or command:COMMAND \
+                    (COMMAND is called with the input, output and expected output files and must exit with 0).\
+                    \nWith --jobs N, up to N testcases are run at the same time. Results are still reported in order.\
+                    \nWith --watch, the files (or directories) are polled for changes until interrupted with Ctrl-C.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Measure how long a solution takes on each testcase of current clash")
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution"))
+                .arg(arg!(--"lang" <LANGUAGE> "use the build command, command and timeout configured for LANGUAGE"))
+                .arg(
+                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
+                        .value_parser(value_parser!(f64))
+                        .default_value("5")
+                )
+                .arg(
+                    arg!(--"checker" <CHECKER> "how to compare the output with the expected output (see below)")
+                        .default_value("exact")
+                )
+                .arg(
+                    arg!(-'n' --"runs" <N> "how many times to run each testcase")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("10")
+                )
+                .arg(arg!(--"only-tests" "skip validators"))
+                .arg(arg!(--"only-validators" "skip tests (run only validators)").conflicts_with("only-tests"))
+                .arg(
+                    arg!(--"testcases" <TESTCASE_INDICES> "indices of the testcases to run (separated by commas)")
+                        .value_parser(value_parser!(u64).range(1..99))
+                        .value_delimiter(',')
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Runs every testcase --runs times in a row and reports the minimum, median and maximum wall time.\
+                    \nPeak memory usage is only measured on Linux.\
                     \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
                 )
         )
+        .subcommand(
+            Command::new("score")
+                .about("Count the characters of a solution like CodinGame does in shortest mode")
+                .arg(arg!(<SOURCE_FILE> "source file of the solution").value_parser(value_parser!(PathBuf)))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Prints the code length and the personal best for the current clash in the same language.\
+                    \nPersonal bests are recorded by `coctus run --source FILE` when every testcase passes."
+                )
+        )
+        .subcommand(
+            Command::new("save")
+                .about("Save a solution of the current clash")
+                .arg(arg!(<FILE> "source file of the solution").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--"lang" <LANGUAGE> "language to save the solution under"))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Solutions are saved in the data directory, one per clash and language. The language is --lang,\
+                    \nor else the language of coctus.toml whose source is FILE, or else the extension of FILE.\
+                    \n`coctus run --source FILE` saves the solution automatically when every testcase passes.\
+                    \nSaved solutions can be run again with `coctus run --saved LANGUAGE`, which builds and runs a\
+                    \ncopy of the solution in a scratch directory with the commands configured for LANGUAGE."
+                )
+        )
+        .subcommand(
+            Command::new("solutions")
+                .about("List the clashes with saved solutions, or print the saved solutions of a clash")
+                .arg(arg!(--"lang" <LANGUAGE> "only print the solution in LANGUAGE"))
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+        )
         .subcommand(
             Command::new("status").about("Show status information")
         )
+        .subcommand(
+            Command::new("clash")
+                .about("Play timed rounds like in a Clash of Code")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("start")
+                        .about("Start a round on a random clash and start the timer")
+                        .arg(
+                            arg!(--"mode" <MODE> "game mode of the round (random if not given)")
+                                .value_parser(["fastest", "shortest", "reverse"])
+                        )
+                        .arg(
+                            arg!(--"minutes" <N> "time limit of the round")
+                                .value_parser(value_parser!(u64))
+                                .default_value("15")
+                        )
+                )
+                .subcommand(
+                    Command::new("submit")
+                        .about("End the round with the result of the last `coctus run`")
+                        .arg(arg!(--"source" <FILE> "source file of the solution, needed in shortest mode")
+                            .value_parser(value_parser!(PathBuf)))
+                        .arg(arg!(--"lang" <LANGUAGE> "use the source file configured for LANGUAGE"))
+                )
+                .subcommand(Command::new("status").about("Show the rounds of the session"))
+                .subcommand(Command::new("end").about("Show the results of the session and end it"))
+                .after_help(
+                    "A session is a sequence of rounds. `coctus clash start` picks a clash that was not played\
+                    \nin the session yet and starts the timer. `coctus show` displays the remaining time, every\
+                    \n`coctus run` is logged and `coctus clash submit` records the score of the last run, the\
+                    \nelapsed time and (in shortest mode) the code length."
+                )
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show the recorded runs of solutions, most recent first")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "only show runs of this clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .arg(
+                    arg!(-'n' --"limit" <N> "how many runs to show")
+                        .value_parser(value_parser!(usize))
+                        .default_value("20")
+                )
+        )
+        .subcommand(
+            Command::new("stats").about("Summarize the solve rate of recorded runs by mode and topic")
+        )
         .subcommand(
             Command::new("fetch")
                 .about("Fetch a clash from codingame.com and save it locally")
@@ -124,6 +552,171 @@ fn cli() -> clap::Command {
                     \n (1) https://www.codingame.com/contribute/community"
                 )
         )
+        .subcommand(
+            Command::new("new")
+                .about("Create a clash locally, as a directory of files to edit")
+                .arg(arg!(<DIR> "directory to create the files in").value_parser(value_parser!(PathBuf)))
+                .arg(arg!(--"title" <TITLE> "title of the clash").default_value("Untitled clash"))
+                .after_help(
+                    "Creates clash.toml (handle, title, type, modes, author, topics and testcases), statement.md,\
+                    \ninput.md, output.md, constraints.md and stub.txt (stub generator) with an example clash.\
+                    \nThe statement and descriptions use CodinGame formatting. Once edited, use `coctus pack DIR`\
+                    \nto add the clash to the local clashes."
+                )
+        )
+        .subcommand(
+            Command::new("pack")
+                .about("Assemble a clash created with `coctus new` and add it to the local clashes")
+                .arg(arg!(<DIR> "directory of the clash").value_parser(value_parser!(PathBuf)))
+                .arg(
+                    arg!(--"output" <FILE> "write the clash JSON to FILE (- for STDOUT) instead")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .after_help(
+                    "The clash is written in the same JSON format as fetched clashes, so every other command works\
+                    \non it. Packing again replaces the previous version of the clash."
+                )
+        )
+        .subcommand(
+            Command::new("lint")
+                .about("Check the formatting of the statement and the testcases of a clash")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .arg(
+                    arg!(--"dir" <DIR> "check the clash created with `coctus new` in DIR instead")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with("PUBLIC_HANDLE")
+                )
+                .arg(
+                    arg!(--"format" <FORMAT> "print the problems as JSON instead of the normal output")
+                        .value_parser(["json"])
+                )
+                .after_help(
+                    "Reports unbalanced [[ ]], {{ }} and << >> tags, obsolete ``` formatting, trailing whitespace\
+                    \nin expected outputs, duplicate testcases and validators with the same input as a test.\
+                    \nExits with an error if any problem is found."
+                )
+        )
+        .subcommand(
+            Command::new("check-inputs")
+                .about("Check that the testcase inputs follow the input format of the stub generator")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .arg(
+                    arg!(--"from-file" <STUBFILE> "use the stub generator in STUBFILE instead of the one of the clash")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .after_help(
+                    "Reads every input (including custom testcases) the way the stub would: each line the stub\
+                    \nreads must be present with the right number of values, ints and floats must parse, words and\
+                    \nstrings must fit in their maximum length and loops must match their counts. The first line\
+                    \nand token where an input diverges is reported. Exits with an error if any input is invalid."
+                )
+        )
+        .subcommand(
+            Command::new("gen-input")
+                .about("Print a random input that follows the input format of the stub generator")
+                .arg(
+                    arg!(--"range" <RANGE> ... "range of a variable, e.g. N=1..100 (values, loop counts or lengths)")
+                        .value_parser(parse_variable_range)
+                )
+                .arg(arg!(--"seed" <SEED> "seed of the random generator").value_parser(value_parser!(u64)))
+                .arg(
+                    arg!(--"from-file" <STUBFILE> "use the stub generator in STUBFILE instead of the one of the clash")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Ints, longs and floats are picked in the --range of their variable, words and strings get\
+                    \na length in the --range of their variable (up to their maximum length). Loop counts are\
+                    \nvariables too, so their range controls the size of the input. Variables without a range use\
+                    \n1..10. The same seed always gives the same input; without --seed a random seed is used and\
+                    \nprinted to STDERR."
+                )
+        )
+        .subcommand(
+            Command::new("stress")
+                .about("Compare a solution with a reference solution on random inputs")
+                .arg(arg!(--"reference" <COMMAND> "command that executes the reference solution").required(true))
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution"))
+                .arg(arg!(--"lang" <LANGUAGE> "use the build command, command and timeout configured for LANGUAGE"))
+                .arg(
+                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
+                        .value_parser(value_parser!(f64))
+                        .default_value("5")
+                )
+                .arg(
+                    arg!(--"checker" <CHECKER> "how to compare the output with the one of the reference")
+                        .default_value("exact")
+                )
+                .arg(
+                    arg!(--"range" <RANGE> ... "range of a variable, e.g. N=1..100 (values, loop counts or lengths)")
+                        .value_parser(parse_variable_range)
+                )
+                .arg(arg!(--"seed" <SEED> "seed of the random generator").value_parser(value_parser!(u64)))
+                .arg(
+                    arg!(--"iterations" <N> "how many inputs to try")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("100")
+                )
+                .arg(
+                    arg!(--"max-shrinks" <N> "how many smaller inputs to try once the outputs differ")
+                        .value_parser(value_parser!(u64))
+                        .default_value("500")
+                )
+                .arg(
+                    arg!(--"from-file" <STUBFILE> "use the stub generator in STUBFILE instead of the one of the clash")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Generates inputs like `coctus gen-input` and runs both the --command and the --reference on\
+                    \neach of them, until their outputs differ. The input is then shrunk (fewer loop iterations,\
+                    \nshorter words, numbers closer to zero) while the outputs still differ, and saved as a custom\
+                    \ntestcase with the output of the reference as expected output (see `coctus addtest`).\
+                    \nThe same seed always gives the same inputs; without --seed a random seed is used and printed\
+                    \nto STDERR. Exits with an error if the outputs differ.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("addtest")
+                .about("Add a custom testcase to the current clash")
+                .arg(arg!(--"title" <TITLE> "title of the testcase").default_value("Custom testcase"))
+                .arg(arg!(--"input" <TEXT> "input of the testcase").required_unless_present("input-file"))
+                .arg(
+                    arg!(--"input-file" <FILE> "read the input from FILE (- for STDIN)")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with("input")
+                )
+                .arg(arg!(--"output" <TEXT> "expected output of the testcase").required_unless_present("output-file"))
+                .arg(
+                    arg!(--"output-file" <FILE> "read the expected output from FILE (- for STDIN)")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with("output")
+                )
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+                .after_help(
+                    "Custom testcases are stored next to the clash, so fetching the clash again keeps them.\
+                    \n`coctus run` and `coctus showtests` include them after the testcases of the clash, with\
+                    \n[custom] at the end of their title. Use --no-custom or --only-custom to skip or select them.\
+                    \nA single trailing newline is removed from the contents of --input-file and --output-file."
+                )
+        )
         .subcommand(
             Command::new("showtests")
                 .about("Print testcases and validators of current clash")
@@ -136,6 +729,10 @@ fn cli() -> clap::Command {
                 )
                 .arg(arg!(--"in" "only print the testcase input"))
                 .arg(arg!(--"out" "only print the testcase output").conflicts_with("in"))
+                .arg(arg!(--"only-tests" "skip validators"))
+                .arg(arg!(--"only-validators" "only print validators").conflicts_with("only-tests"))
+                .arg(arg!(--"no-custom" "skip the testcases added with `coctus addtest`"))
+                .arg(arg!(--"only-custom" "only print the testcases added with `coctus addtest`").conflicts_with("no-custom"))
                 .arg(
                     arg!([TESTCASE] ... "indices of the testcases to print (default: all)")
                         .value_parser(value_parser!(u64).range(1..99))
@@ -143,24 +740,112 @@ fn cli() -> clap::Command {
                 )
         )
         .subcommand(
-            Command::new("json")
-                .about("Print the raw source JSON of a clash")
+            Command::new("tui")
+                .about("Browse and solve clashes in a full-screen terminal UI")
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution"))
+                .arg(arg!(--"lang" <LANGUAGE> "use the build command, command and timeout configured for LANGUAGE"))
+                .arg(
+                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
+                        .value_parser(value_parser!(f64))
+                        .default_value("5")
+                )
+                .arg(
+                    arg!(--"checker" <CHECKER> "how to compare the output with the expected output (see `coctus run --help`)")
+                        .default_value("exact")
+                )
+                .arg(
+                    arg!(--"show-whitespace" [BOOL] "render ⏎ and • in place of newlines and spaces")
+                        // This means show-whitespace=1 also works
+                        .value_parser(clap::builder::BoolishValueParser::new())
+                        .default_value("true")
+                        .default_missing_value("true")
+                )
                 .arg(
                     arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
                         .value_parser(value_parser!(PublicHandle))
                 )
+                .after_help(
+                    "Shows the statement of the clash next to its testcases. Press r to build and run the solution\
+                    \nagainst all testcases, Tab to switch between a testcase and the diff of its last run,\
+                    \nn to move on to a random clash and q to quit.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
         )
         .subcommand(
-            Command::new("generate-stub")
-                .alias("gen")
-                .about("Generate input handling code for a given language")
-                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language of the solution stub"))
+            Command::new("host")
+                .about("Host a clash for players on the local network")
                 .arg(
-                    arg!(--"from-file" <STUBFILE> "Generate stub from a stub generator file instead of the current clash")
-                        .value_parser(clap::value_parser!(PathBuf))
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash (random if not given)")
+                        .value_parser(value_parser!(PublicHandle))
                 )
-                .arg(arg!(--"from-reference" "Generate stub from the reference stub generator instead of the current clash").conflicts_with("from-file"))
-                .after_help(
+                .arg(
+                    arg!(--"port" <PORT> "TCP port to listen on")
+                        .value_parser(value_parser!(u16))
+                        .default_value("4242")
+                )
+                .arg(
+                    arg!(--"mode" <MODE> "game mode (random if not given)")
+                        .value_parser(["fastest", "shortest", "reverse"])
+                )
+                .arg(
+                    arg!(--"minutes" <N> "time limit")
+                        .value_parser(value_parser!(u64))
+                        .default_value("15")
+                )
+                .after_help(
+                    "Every player that joins receives the clash. Whenever a player submits, the leaderboard\
+                    \nis sent to all players: higher scores first, then shorter code (in shortest mode), then\
+                    \nfaster submissions."
+                )
+        )
+        .subcommand(
+            Command::new("join")
+                .about("Join a clash hosted on the local network")
+                .arg(arg!(<ADDR> "address of the host, e.g. 192.168.1.20:4242 (the port defaults to 4242)"))
+                .arg(arg!(--"name" <NAME> "name on the leaderboard (defaults to $USER)"))
+                .arg(arg!(--"build-command" <COMMAND> "command that compiles the solution"))
+                .arg(arg!(--"command" <COMMAND> "command that executes the solution"))
+                .arg(arg!(--"lang" <LANGUAGE> "use the build command, command, timeout and source file configured for LANGUAGE"))
+                .arg(
+                    arg!(--"source" <FILE> "source file of the solution, used to count its code length")
+                        .value_parser(value_parser!(PathBuf))
+                )
+                .arg(
+                    arg!(--"timeout" <SECONDS> "how many seconds before execution is timed out (0 for no timeout)")
+                        .value_parser(value_parser!(f64))
+                        .default_value("5")
+                )
+                .arg(
+                    arg!(--"checker" <CHECKER> "how to compare the output with the expected output (see `coctus run --help`)")
+                        .default_value("exact")
+                )
+                .after_help(
+                    "The clash sent by the host is stored locally and becomes the current clash, so `coctus show`\
+                    \nand `coctus run` work as usual in another terminal. Press Enter to run the solution against\
+                    \nall testcases and submit the result.\
+                    \nIMPORTANT: The commands you provide will be executed without any sandboxing. Only run code you trust!"
+                )
+        )
+        .subcommand(
+            Command::new("json")
+                .about("Print the raw source JSON of a clash")
+                .arg(
+                    arg!([PUBLIC_HANDLE] "hexadecimal handle of the clash")
+                        .value_parser(value_parser!(PublicHandle))
+                )
+        )
+        .subcommand(
+            Command::new("generate-stub")
+                .alias("gen")
+                .about("Generate input handling code for a given language")
+                .arg(arg!(<PROGRAMMING_LANGUAGE> "Programming language of the solution stub"))
+                .arg(
+                    arg!(--"from-file" <STUBFILE> "Generate stub from a stub generator file instead of the current clash")
+                        .value_parser(clap::value_parser!(PathBuf))
+                )
+                .arg(arg!(--"from-reference" "Generate stub from the reference stub generator instead of the current clash").conflicts_with("from-file"))
+                .after_help(
                     "Prints boilerplate code for the input of the current clash.\
                     \nIntended to be piped to a file.\
                     \nExamples:\
@@ -187,7 +872,14 @@ fn cli() -> clap::Command {
 struct App {
     clash_dir: PathBuf,
     current_clash_file: PathBuf,
+    personal_bests_file: PathBuf,
+    index_file: PathBuf,
+    history_file: PathBuf,
+    review_file: PathBuf,
+    session_file: PathBuf,
+    solutions_dir: PathBuf,
     stub_templates_dir: PathBuf,
+    config_files: Vec<PathBuf>,
 }
 
 impl App {
@@ -195,8 +887,58 @@ impl App {
         App {
             clash_dir: data_dir.join("clashes"),
             current_clash_file: data_dir.join("current"),
+            personal_bests_file: data_dir.join("personal_bests.json"),
+            index_file: data_dir.join("index.json"),
+            history_file: data_dir.join("history.jsonl"),
+            review_file: data_dir.join("review.json"),
+            session_file: data_dir.join("session.json"),
+            solutions_dir: data_dir.join("solutions"),
             stub_templates_dir: config_dir.join("stub_templates"),
+            // The config file in the current directory takes precedence
+            config_files: vec![config_dir.join("coctus.toml"), PathBuf::from("coctus.toml")],
+        }
+    }
+
+    /// Settings of the language selected with --lang. When neither --lang nor
+    /// --command is given, the default language of the config is used (if
+    /// there is one).
+    fn selected_language(&self, args: &ArgMatches) -> Result<LanguageConfig> {
+        let config = Config::load(&self.config_files)?;
+        let name = match args.get_one::<String>("lang") {
+            Some(name) => name.to_owned(),
+            None if args.get_one::<String>("command").is_some() => return Ok(LanguageConfig::default()),
+            None => match config.default_language {
+                Some(ref name) => name.to_owned(),
+                None => return Ok(LanguageConfig::default()),
+            },
+        };
+        config.language(&name).cloned()
+    }
+
+    /// Language of a solution, used to key it in the history, the personal
+    /// bests and the solution archive: --lang, or else the configured language
+    /// with `source_file` as its source, or else the extension of
+    /// `source_file`. Without a source file, the solution is identified by
+    /// its --command, or else by the default language.
+    fn solution_language(&self, args: &ArgMatches, source_file: Option<&Path>) -> Result<String> {
+        if let Some(name) = args.try_get_one::<String>("lang").ok().flatten() {
+            return Ok(name.to_owned())
+        }
+        let config = Config::load(&self.config_files)?;
+        if let Some(source_file) = source_file {
+            let configured =
+                config.languages.iter().find(|(_, lang)| lang.source.as_deref() == Some(source_file));
+            if let Some((name, _)) = configured {
+                return Ok(name.to_owned())
+            }
+            if let Some(extension) = source_file.extension().and_then(|ext| ext.to_str()) {
+                return Ok(extension.to_owned())
+            }
+        }
+        if let Some(command) = args.try_get_one::<String>("command").ok().flatten() {
+            return Ok(command.to_owned())
         }
+        Ok(config.default_language.unwrap_or_else(|| String::from("unknown")))
     }
 
     // This may fail the very first time we call `show` if `next` was never run.
@@ -206,40 +948,101 @@ impl App {
         PublicHandle::from_str(&content)
     }
 
-    fn clashes(&self) -> Result<std::fs::ReadDir> {
-        std::fs::read_dir(&self.clash_dir).with_context(|| "No clashes stored")
+    /// The index of stored clashes, updated with any changes to the clash
+    /// directory.
+    fn clash_index(&self) -> Result<ClashIndex> {
+        let mut index = ClashIndex::load(&self.index_file);
+        if index.sync(&self.clash_dir)? {
+            index.save(&self.index_file)?;
+        }
+        Ok(index)
     }
 
     fn random_handle(&self) -> Result<PublicHandle> {
-        let mut rng = rand::thread_rng();
-        if let Ok(entry) = self.clashes()?.choose(&mut rng).expect("No clashes to choose from!") {
-            let filename =
-                entry.file_name().into_string().expect("unable to convert OsString to String (?!?)");
-            PublicHandle::from_str(match filename.strip_suffix(".json") {
-                Some(handle) => handle,
-                None => &filename,
-            })
-        } else {
-            Err(anyhow!("Unable to randomize next clash"))
+        self.random_handle_matching(&ClashFilter::default())
+    }
+
+    fn random_handle_matching(&self, filter: &ClashFilter) -> Result<PublicHandle> {
+        let index = self.clash_index()?;
+        if index.is_empty() {
+            return Err(anyhow!("No clashes to choose from! Use `coctus fetch` to download some."))
         }
+        let entry = index
+            .entries()
+            .filter(|entry| filter.matches_entry(entry))
+            .choose(&mut rand::thread_rng())
+            .context("No stored clash matches the filters")?;
+        Ok(entry.handle.clone())
     }
 
-    fn random_handle_with_modes(&self, fastest: bool, shortest: bool, reverse: bool) -> Result<PublicHandle> {
-        let max_attemps = 100;
-        for _ in 0..max_attemps {
-            let handle = self.random_handle()?;
-            let clash = self.read_clash(&handle)?;
-            if (!reverse || clash.is_reverse())
-                && (!fastest || clash.is_fastest())
-                && (!shortest || clash.is_shortest())
-            {
-                return Ok(handle)
+    /// The stored clash matching `filter` that is due for review the longest.
+    fn handle_to_review(&self, filter: &ClashFilter) -> Result<PublicHandle> {
+        let index = self.clash_index()?;
+        let schedule = ReviewSchedule::load(&self.review_file)?;
+        let candidates = index
+            .entries()
+            .filter(|entry| filter.matches_entry(entry))
+            .map(|entry| &entry.handle);
+        let handle = schedule.next_due(candidates, unix_timestamp(SystemTime::now()));
+        handle.cloned().context("No stored clash matches the filters")
+    }
+
+    /// When the current clash was picked, in seconds since the Unix epoch.
+    fn selected_at(&self) -> Option<u64> {
+        let modified = std::fs::metadata(&self.current_clash_file).and_then(|metadata| metadata.modified());
+        modified.ok().map(unix_timestamp)
+    }
+
+    /// Grades the first solve of the current clash since it was picked by how
+    /// long it took.
+    fn review_solved_clash(&self, handle: &PublicHandle) -> Result<()> {
+        let (Ok(current), Some(selected_at)) = (self.current_handle(), self.selected_at()) else {
+            return Ok(())
+        };
+        let mut schedule = ReviewSchedule::load(&self.review_file)?;
+        let reviewed = schedule.get(handle).is_some_and(|card| card.last_review > selected_at);
+        if current != *handle || reviewed {
+            return Ok(())
+        }
+        let now = unix_timestamp(SystemTime::now());
+        schedule.review(handle, grade_solve(Some(now.saturating_sub(selected_at))), now);
+        schedule.save(&self.review_file)
+    }
+
+    /// Grades the current clash as failed if it was run but never solved
+    /// since it was picked. Called before moving on to another clash.
+    fn review_abandoned_clash(&self) -> Result<()> {
+        let (Ok(current), Some(selected_at)) = (self.current_handle(), self.selected_at()) else {
+            return Ok(())
+        };
+        let mut schedule = ReviewSchedule::load(&self.review_file)?;
+        if schedule.get(&current).is_some_and(|card| card.last_review > selected_at) {
+            return Ok(())
+        }
+        let history = SolveHistory::load(&self.history_file)?;
+        let mut runs = history
+            .entries()
+            .iter()
+            .filter(|entry| entry.handle == current && entry.timestamp >= selected_at)
+            .peekable();
+        if runs.peek().is_none() || runs.any(|entry| entry.is_solved()) {
+            return Ok(())
+        }
+        schedule.review(&current, FAILED, unix_timestamp(SystemTime::now()));
+        schedule.save(&self.review_file)
+    }
+
+    /// Stored clashes that match `filter`.
+    fn filtered_clashes(&self, filter: &ClashFilter) -> Result<Vec<IndexEntry>> {
+        let index = self.clash_index()?;
+        let mut entries = Vec::new();
+        for entry in index.entries().filter(|entry| filter.matches_entry(entry)) {
+            if filter.has_search_terms() && !filter.matches_text(&self.read_clash(&entry.handle)?) {
+                continue
             }
+            entries.push(entry.clone());
         }
-        Err(anyhow!(format!(
-            "Failed to find a clash with the required modes after {} attempts.",
-            max_attemps
-        )))
+        Ok(entries)
     }
 
     fn read_clash(&self, handle: &PublicHandle) -> Result<Clash> {
@@ -261,6 +1064,11 @@ impl App {
         let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
         let ostyle = OutputStyle::from_env(show_whitespace);
 
+        let session = ClashSession::load(&self.session_file)?;
+        if let Some(round) = session.round_of(&handle) {
+            ostyle.print_round_timer(round, unix_timestamp(SystemTime::now()));
+        }
+
         // --reverse flag
         if args.get_flag("reverse") {
             if clash.is_reverse() {
@@ -285,108 +1093,548 @@ impl App {
     fn next(&self, args: &ArgMatches) -> Result<()> {
         let next_handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
             Some(h) => h.to_owned(),
+            None if args.get_flag("review") => self.handle_to_review(&ClashFilter::from_args(args))?,
             None => {
-                let fastest = args.get_flag("fastest");
-                let shortest = args.get_flag("shortest");
-                let reverse = args.get_flag("reverse");
-                if reverse || fastest || shortest {
-                    self.random_handle_with_modes(fastest, shortest, reverse)?
-                } else {
-                    self.random_handle()?
+                let mut filter = ClashFilter::from_args(args);
+                if args.get_flag("unsolved") {
+                    filter.excluded = SolveHistory::load(&self.history_file)?.solved_handles();
                 }
+                self.random_handle_matching(&filter)?
             }
         };
-        println!(" Changed clash to https://codingame.com/contribute/view/{}", next_handle);
-        println!(" Local file: {}/{}.json", &self.clash_dir.to_str().unwrap(), next_handle);
+        self.change_clash(&next_handle)?;
+        self.print_changed_clash(&next_handle);
+        Ok(())
+    }
+
+    /// Make `next_handle` the current clash. The clash it replaces is
+    /// reviewed as failed if it was abandoned unsolved.
+    fn change_clash(&self, next_handle: &PublicHandle) -> Result<()> {
+        self.review_abandoned_clash()?;
         std::fs::write(&self.current_clash_file, next_handle.to_string())?;
         Ok(())
     }
 
+    fn print_changed_clash(&self, handle: &PublicHandle) {
+        println!(" Changed clash to https://codingame.com/contribute/view/{}", handle);
+        println!(" Local file: {}/{}.json", &self.clash_dir.to_str().unwrap(), handle);
+    }
+
+    fn clash(&self, args: &ArgMatches) -> Result<()> {
+        match args.subcommand() {
+            Some(("start", args)) => self.clash_start(args),
+            Some(("submit", args)) => self.clash_submit(args),
+            Some(("status", _)) => self.clash_status(),
+            Some(("end", _)) => self.clash_end(),
+            _ => Err(anyhow!("Unimplemented clash subcommand")),
+        }
+    }
+
+    /// Starts a new round of the session on a random clash that was not
+    /// played in it yet.
+    fn clash_start(&self, args: &ArgMatches) -> Result<()> {
+        let mut session = ClashSession::load(&self.session_file)?;
+        if let Some(round) = session.current_round() {
+            return Err(anyhow!(
+                "The round on clash {} is not over yet, use `coctus clash submit` first",
+                round.handle
+            ))
+        }
+
+        let mode = args.get_one::<String>("mode");
+        let filter = ClashFilter {
+            fastest: mode.is_some_and(|mode| mode == "fastest"),
+            shortest: mode.is_some_and(|mode| mode == "shortest"),
+            reverse: mode.is_some_and(|mode| mode == "reverse"),
+            excluded: session.played().map(PublicHandle::to_string).collect(),
+            ..Default::default()
+        };
+        let handle = self.random_handle_matching(&filter)?;
+        let mode = match mode {
+            Some(mode) => mode.to_owned(),
+            None => random_mode(&self.read_clash(&handle)?),
+        };
+        let minutes = *args.get_one::<u64>("minutes").unwrap_or(&15);
+
+        let now = unix_timestamp(SystemTime::now());
+        session.start_round(handle.clone(), mode.clone(), minutes * 60, now)?;
+        self.change_clash(&handle)?;
+        self.print_changed_clash(&handle);
+        session.save(&self.session_file)?;
+        println!(
+            "Round {} started: {} minutes, {} mode. Good luck!",
+            session.rounds.len(),
+            minutes,
+            mode
+        );
+        Ok(())
+    }
+
+    /// Ends the current round with the result of its last run.
+    fn clash_submit(&self, args: &ArgMatches) -> Result<()> {
+        let mut session = ClashSession::load(&self.session_file)?;
+        let round = session
+            .current_round()
+            .context("No round is being played, use `coctus clash start`")?;
+        let solved = round.runs.last().is_some_and(|run| run.passed == run.total);
+
+        let code_length = if round.mode == "shortest" {
+            let lang = self.selected_language(args)?;
+            let source_file = args
+                .get_one::<PathBuf>("source")
+                .or(lang.source.as_ref())
+                .context("Shortest mode needs the source file to count its code length (use --source)")?;
+            Some(self.record_code_length(args, &round.handle, source_file, solved)?.0)
+        } else {
+            None
+        };
+
+        let now = unix_timestamp(SystemTime::now());
+        session.submit(code_length, now)?;
+        session.save(&self.session_file)?;
+        OutputStyle::from_env(false).print_rounds(&session.rounds, now);
+        println!("Use `coctus clash start` for the next round or `coctus clash end` to finish the session.");
+        Ok(())
+    }
+
+    fn clash_status(&self) -> Result<()> {
+        let session = ClashSession::load(&self.session_file)?;
+        if session.rounds.is_empty() {
+            println!("No session going on, use `coctus clash start`");
+            return Ok(())
+        }
+        OutputStyle::from_env(false).print_rounds(&session.rounds, unix_timestamp(SystemTime::now()));
+        Ok(())
+    }
+
+    /// Prints the results of the session and forgets it.
+    fn clash_end(&self) -> Result<()> {
+        let session = ClashSession::load(&self.session_file)?;
+        if session.rounds.is_empty() {
+            println!("No session going on");
+            return Ok(())
+        }
+        let now = unix_timestamp(SystemTime::now());
+        let ostyle = OutputStyle::from_env(false);
+        ostyle.print_rounds(&session.rounds, now);
+        let results = session.rounds.iter().filter_map(|round| round.result.as_ref());
+        let (num_submitted, total_score) =
+            results.fold((0, 0), |(count, total), result| (count + 1, total + result.score()));
+        if let Some(average) = total_score.checked_div(num_submitted) {
+            println!("Average score: {}%", average);
+        }
+        std::fs::remove_file(&self.session_file)
+            .with_context(|| format!("Unable to remove {:?}", self.session_file))
+    }
+
+    fn list(&self, args: &ArgMatches) -> Result<()> {
+        let clashes = self.filtered_clashes(&ClashFilter::from_args(args))?;
+
+        if args.get_one::<String>("format").is_some_and(|format| format == "json") {
+            let clashes: Vec<serde_json::Value> = clashes
+                .iter()
+                .map(|entry| {
+                    serde_json::json!({
+                        "handle": entry.handle.to_string(),
+                        "title": entry.title,
+                        "fastest": entry.fastest,
+                        "shortest": entry.shortest,
+                        "reverse": entry.reverse,
+                        "type": ClashFilter::type_name(entry.puzzle_type),
+                        "upvotes": entry.upvotes,
+                        "downvotes": entry.downvotes,
+                        "topics": entry.topics,
+                        "author": entry.author,
+                        "status": entry.status,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&clashes)?);
+            return Ok(())
+        }
+
+        let ostyle = OutputStyle::from_env(false);
+        ostyle.print_clash_table(&clashes);
+        Ok(())
+    }
+
     fn status(&self, _args: &ArgMatches) -> Result<()> {
         println!("Current clash file: {}", self.current_clash_file.display());
         match self.current_handle() {
-            Ok(handle) => println!("Current clash: {}", handle),
+            Ok(handle) => match self.read_clash(&handle) {
+                Ok(clash) => {
+                    println!("Current clash: {} ({})", handle, clash.title());
+                    println!("Clash details: {}", clash_metadata(&clash).join(" | "));
+                }
+                Err(_) => println!("Current clash: {}", handle),
+            },
             Err(_) => println!("Current clash: -"),
         }
         println!("Clash dir: {}", self.clash_dir.display());
-        let num_clashes = match self.clashes() {
-            Ok(clashes) => clashes.count(),
+        for config_file in self.config_files.iter().filter(|path| path.is_file()) {
+            println!("Config file: {}", config_file.display());
+        }
+        let num_clashes = match self.clash_index() {
+            Ok(index) => index.len(),
             Err(_) => 0,
         };
         println!("Number of clashes: {}", num_clashes);
         Ok(())
     }
 
+    fn history(&self, args: &ArgMatches) -> Result<()> {
+        let history = SolveHistory::load(&self.history_file)?;
+        let handle = args.get_one::<PublicHandle>("PUBLIC_HANDLE");
+        let limit = *args.get_one::<usize>("limit").unwrap_or(&20);
+        let entries: Vec<HistoryEntry> = history
+            .entries()
+            .iter()
+            .rev()
+            .filter(|entry| handle.map_or(true, |handle| &entry.handle == handle))
+            .take(limit)
+            .cloned()
+            .collect();
+        OutputStyle::from_env(false).print_history(&entries);
+        Ok(())
+    }
+
+    fn stats(&self, _args: &ArgMatches) -> Result<()> {
+        let history = SolveHistory::load(&self.history_file)?;
+        // Topics are only known for clashes that are still stored
+        let index = self.clash_index().unwrap_or_default();
+        OutputStyle::from_env(false).print_stats(&history.stats(&index));
+        Ok(())
+    }
+
     fn run(&self, args: &ArgMatches) -> Result<()> {
+        let Some(watch_paths) = args.get_many::<PathBuf>("watch") else {
+            return self.run_once(args)
+        };
+
+        // A machine readable report must not be interleaved with escape codes
+        let clear_screen = args.get_one::<String>("format").is_none();
+        let mut watcher = Watcher::new(watch_paths.cloned().collect());
+        loop {
+            if clear_screen {
+                // Clear the screen and move the cursor to the top left corner
+                print!("\x1b[2J\x1b[H");
+                std::io::stdout().flush()?;
+            }
+            if let Err(error) = self.run_once(args) {
+                println!("Error: {:#}", error);
+            }
+            println!("\nWatching for changes... (press Ctrl-C to stop)");
+            watcher.wait_for_change();
+        }
+    }
+
+    fn run_once(&self, args: &ArgMatches) -> Result<()> {
         let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
             Some(h) => h.to_owned(),
             None => self.current_handle()?,
         };
 
-        if let Some(mut build_command) = command_from_argument(args.get_one::<String>("build-command"))? {
-            let build = build_command.output()?;
-
-            if !build.status.success() {
-                if !build.stderr.is_empty() {
-                    println!("Build command STDERR:\n{}", String::from_utf8(build.stderr)?);
-                }
-                if !build.stdout.is_empty() {
-                    println!("Build command STDOUT:\n{}", String::from_utf8(build.stdout)?);
-                }
-                return Err(anyhow!("Build failed"))
+        // A saved solution is built and run in a scratch directory with the
+        // commands of its language
+        let saved_language = args.get_one::<String>("saved");
+        let (lang, work_dir) = match saved_language {
+            Some(language) => {
+                let config = Config::load(&self.config_files)?;
+                let has_commands = args.get_one::<String>("command").is_some()
+                    || args.get_one::<String>("build-command").is_some();
+                let lang = match config.language(language) {
+                    Ok(lang) => lang.clone(),
+                    // The commands given on the command line are enough
+                    Err(_) if has_commands => LanguageConfig::default(),
+                    Err(error) => return Err(error),
+                };
+                (lang, Some(self.copy_saved_solution(&handle, language)?))
             }
+            None => (self.selected_language(args)?, None),
+        };
+        build_solution(args, &lang, work_dir.as_deref())?;
+
+        let mut run_command = run_command_from_args(args, &lang)?;
+        if let Some(dir) = work_dir.as_deref() {
+            run_command.current_dir(dir);
         }
 
-        let mut run_command = command_from_argument(args.get_one::<String>("command"))?
-            .expect("clap should ensure `run` can't be executed without a --command");
+        let timeout = timeout_from_args(args, &lang)?;
 
-        let timeout = match *args.get_one::<f64>("timeout").unwrap_or(&5.0) {
-            secs if secs.is_nan() => return Err(anyhow!("Timeout can't be NaN")),
-            secs if secs < 0.0 => return Err(anyhow!("Timeout can't be negative (use 0 for no timeout)")),
-            secs if secs == 0.0 => std::time::Duration::MAX,
-            secs => std::time::Duration::from_micros((secs * 1e6) as u64),
+        let limits = solution::ResourceLimits {
+            memory: args
+                .get_one::<u64>("memory-limit")
+                .map(|megabytes| megabytes.saturating_mul(1024 * 1024)),
+            cpu_time: args.get_one::<u64>("cpu-limit").copied(),
+            output: args.get_one::<u64>("output-limit").map(|kilobytes| kilobytes.saturating_mul(1024)),
         };
 
-        let all_testcases = self.read_clash(&handle)?.testcases().to_owned();
+        let checker = checker_from_args(args)?;
 
-        let testcases: Vec<&Testcase> = if let Some(testcase_indices) = args.get_many::<u64>("testcases") {
-            testcase_indices.map(|idx| &all_testcases[(idx - 1) as usize]).collect()
-        } else {
-            all_testcases.iter().collect()
+        let clash = self.read_clash(&handle)?;
+        let num_clash_testcases = clash.testcases().len();
+        let all_testcases = self.testcases_with_custom(&clash, &handle)?;
+
+        let testcases: Vec<&Testcase> = select_testcases(&all_testcases, args)
+            .into_iter()
+            .filter(|testcase| is_selected_origin(testcase, num_clash_testcases, args))
+            .collect();
+        // A run of only some of the testcases of the clash says nothing about
+        // whether it is solved, so it is not recorded
+        let selected_clash_testcases =
+            testcases.iter().filter(|testcase| testcase.index <= num_clash_testcases).count();
+        let full_run = selected_clash_testcases == num_clash_testcases;
+
+        let report_format = match args.get_one::<String>("format") {
+            Some(format) => Some(ReportFormat::from_str(format)?),
+            None => None,
         };
+        let mut report = SuiteReport::new(handle.to_string(), testcases.iter().copied());
+        let started = Instant::now();
 
-        let num_tests = testcases.len();
-        let suite_run = solution::lazy_run(testcases, &mut run_command, &timeout);
+        let jobs = *args.get_one::<u64>("jobs").unwrap_or(&1) as usize;
+        let suite_run: Box<dyn Iterator<Item = (&Testcase, TestRun)> + '_> = if jobs > 1 {
+            Box::new(solution::parallel_run(testcases, &run_command, &timeout, &limits, &*checker, jobs))
+        } else {
+            let test_runs = solution::lazy_run(testcases, &mut run_command, &timeout, &limits, &*checker);
+            Box::new(test_runs.into_iter())
+        };
 
         let ignore_failures = args.get_flag("ignore-failures");
         let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
         let ostyle = OutputStyle::from_env(show_whitespace);
 
-        let mut num_passed = 0;
-
-        for (testcase, test_result) in suite_run {
-            ostyle.print_result(testcase, &test_result);
+        // Only the testcases of the clash count in the history
+        let mut passed_clash_testcases = 0;
+        for (testcase, test_run) in suite_run {
+            if report_format.is_none() {
+                ostyle.print_result(testcase, &test_run);
+            }
 
-            if test_result.is_success() {
-                num_passed += 1;
-            } else if !ignore_failures {
+            let passed = test_run.is_success();
+            if passed && testcase.index <= num_clash_testcases {
+                passed_clash_testcases += 1;
+            }
+            report.record(testcase, test_run);
+            if !passed && !ignore_failures {
                 break
             }
         }
-        println!("{num_passed}/{num_tests} tests passed");
+
+        let source_file = match &work_dir {
+            Some(_) => None,
+            None => args.get_one::<PathBuf>("source").or(lang.source.as_ref()),
+        };
+        let language = match saved_language {
+            Some(language) => language.to_owned(),
+            None => self.solution_language(args, source_file.map(PathBuf::as_path))?,
+        };
+        let mut history_entry =
+            HistoryEntry::new(handle.clone(), language.clone(), passed_clash_testcases, num_clash_testcases);
+        history_entry.duration = started.elapsed();
+        if full_run {
+            let mut session = ClashSession::load(&self.session_file)?;
+            let round_mode = session.round_of(&handle).map(|round| round.mode.clone());
+            history_entry.mode =
+                args.get_one::<String>("mode").cloned().or(round_mode).or_else(|| only_mode(&clash));
+            SolveHistory::record(&self.history_file, &history_entry)?;
+            let round_run = RoundRun {
+                timestamp: history_entry.timestamp,
+                passed: history_entry.passed,
+                total: history_entry.total,
+            };
+            if session.log_run(&handle, round_run) {
+                session.save(&self.session_file)?;
+            }
+            if history_entry.is_solved() {
+                self.review_solved_clash(&handle)?;
+            }
+        }
+
+        if let Some(format) = report_format {
+            println!("{}", report.render(format));
+        } else {
+            // Tests and validators are reported separately like CodinGame does
+            let mut summary = Vec::new();
+            let (passed_tests, num_tests) = report.tests_passed();
+            if num_tests > 0 {
+                summary.push(format!("tests: {}/{}", passed_tests, num_tests));
+            }
+            let (passed_validators, num_validators) = report.validators_passed();
+            if num_validators > 0 {
+                summary.push(format!("validators: {}/{}", passed_validators, num_validators));
+            }
+            if summary.is_empty() {
+                println!("No testcases selected");
+            } else {
+                println!("{}", summary.join(", "));
+            }
+        }
+
+        if let Some(source_file) = source_file {
+            let (code_length, previous_best) =
+                self.record_code_length(args, &handle, source_file, history_entry.is_solved())?;
+            if report_format.is_none() {
+                print_code_length(code_length, previous_best, history_entry.is_solved());
+            }
+            if history_entry.is_solved() {
+                let archive = SolutionArchive::new(self.solutions_dir.clone());
+                let saved = archive.save(&handle, &language, source_file)?;
+                if report_format.is_none() {
+                    println!("Solution saved to {}", saved.display());
+                }
+            }
+        }
 
         // Move on to next clash if --auto-advance is set
-        if num_passed == num_tests && args.get_flag("auto-advance") {
-            let next_handle = self.random_handle()?;
-            std::fs::write(&self.current_clash_file, next_handle.to_string())?;
-            println!("Moving on to next clash...");
+        if report.all_passed() && args.get_flag("auto-advance") {
+            // Prefer clashes that were not solved yet, but fall back to any
+            let unsolved = ClashFilter {
+                excluded: SolveHistory::load(&self.history_file)?.solved_handles(),
+                ..Default::default()
+            };
+            let next_handle = self.random_handle_matching(&unsolved).or_else(|_| self.random_handle())?;
+            self.change_clash(&next_handle)?;
+            if report_format.is_none() {
+                println!("Moving on to next clash...");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn bench(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+
+        let lang = self.selected_language(args)?;
+        build_solution(args, &lang, None)?;
+
+        let mut run_command = run_command_from_args(args, &lang)?;
+        let timeout = timeout_from_args(args, &lang)?;
+        let runs = *args.get_one::<u64>("runs").unwrap_or(&10) as usize;
+        let limits = solution::ResourceLimits::default();
+        let checker = checker_from_args(args)?;
+
+        let all_testcases = self.read_clash(&handle)?.testcases().to_owned();
+        let ostyle = OutputStyle::from_env(false);
+
+        for testcase in select_testcases(&all_testcases, args) {
+            let stats =
+                solution::bench_testcase(testcase, &mut run_command, &timeout, &limits, &*checker, runs);
+            ostyle.print_bench(testcase, &stats);
+        }
+
+        Ok(())
+    }
+
+    /// Copies the saved solution of the clash in `language` to a scratch
+    /// directory and returns the directory, which is removed once dropped.
+    fn copy_saved_solution(&self, handle: &PublicHandle, language: &str) -> Result<ScratchDir> {
+        let archive = SolutionArchive::new(self.solutions_dir.clone());
+        let saved = archive
+            .get(handle, language)
+            .with_context(|| format!("No saved {} solution for clash {}", language, handle))?;
+        let work_dir = std::env::temp_dir().join(format!("coctus-saved-{}", std::process::id()));
+        if work_dir.exists() {
+            std::fs::remove_dir_all(&work_dir)?;
+        }
+        std::fs::create_dir_all(&work_dir)?;
+        let work_dir = ScratchDir(work_dir);
+        let file_name = saved.file_name().context("Saved solution should be a file")?;
+        std::fs::copy(&saved, work_dir.join(file_name))?;
+        Ok(work_dir)
+    }
+
+    fn save(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let source_file = args.get_one::<PathBuf>("FILE").context("Should have a source file")?;
+        let language = self.solution_language(args, Some(source_file))?;
+        let saved = SolutionArchive::new(self.solutions_dir.clone()).save(&handle, &language, source_file)?;
+        println!("Saved {} solution of clash {} to {}", language, handle, saved.display());
+        Ok(())
+    }
+
+    /// Lists the clashes with saved solutions, or prints the solutions of one
+    /// clash.
+    fn solutions(&self, args: &ArgMatches) -> Result<()> {
+        let archive = SolutionArchive::new(self.solutions_dir.clone());
+        let ostyle = OutputStyle::from_env(false);
+        let Some(handle) = args.get_one::<PublicHandle>("PUBLIC_HANDLE") else {
+            let handles = archive.handles()?;
+            if handles.is_empty() {
+                println!("No saved solutions yet");
+            }
+            for handle in handles {
+                let title =
+                    self.read_clash(&handle).map(|clash| clash.title().to_string()).unwrap_or_default();
+                let languages = archive.languages(&handle)?.join(", ");
+                println!("{}  {}  {}", handle, ostyle.title.paint(title), languages);
+            }
+            return Ok(())
+        };
+
+        let mut languages = archive.languages(handle)?;
+        if let Some(language) = args.get_one::<String>("lang") {
+            languages.retain(|saved_language| saved_language == language);
+        }
+        if languages.is_empty() {
+            return Err(anyhow!("No saved solutions for clash {}", handle))
         }
+        for language in languages {
+            let Some(path) = archive.get(handle, &language) else {
+                continue
+            };
+            let source =
+                std::fs::read_to_string(&path).with_context(|| format!("Unable to read {:?}", path))?;
+            println!("{}", ostyle.title.paint(format!("=== {} ({}) ===", language, path.display())));
+            println!("{}", source.trim_end());
+        }
+        Ok(())
+    }
+
+    /// Counts the characters in `source_file` and records them as a personal
+    /// best if the solution `passed`. Returns the code length and the previous
+    /// personal best.
+    fn record_code_length(
+        &self,
+        args: &ArgMatches,
+        handle: &PublicHandle,
+        source_file: &Path,
+        passed: bool,
+    ) -> Result<(usize, Option<usize>)> {
+        let source = std::fs::read_to_string(source_file)
+            .with_context(|| format!("Unable to read source file {:?}", source_file))?;
+        let code_length = solution::code_length(&source);
+        let language = self.solution_language(args, Some(source_file))?;
 
+        let mut personal_bests = PersonalBests::load(&self.personal_bests_file)?;
+        let previous_best = personal_bests.get(handle, &language);
+        if passed && personal_bests.update(handle, &language, code_length) {
+            personal_bests.save(&self.personal_bests_file)?;
+        }
+        Ok((code_length, previous_best))
+    }
+
+    fn score(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let source_file = args.get_one::<PathBuf>("SOURCE_FILE").context("Should have a source file")?;
+        let (code_length, previous_best) = self.record_code_length(args, &handle, source_file, false)?;
+        print_code_length(code_length, previous_best, false);
         Ok(())
     }
 
     fn fetch(&self, args: &ArgMatches) -> Result<()> {
         std::fs::create_dir_all(&self.clash_dir)?;
+        let mut index = self.clash_index()?;
         let handles = args
             .get_many::<PublicHandle>("PUBLIC_HANDLE")
             .with_context(|| "Should have many handles")?;
@@ -402,14 +1650,385 @@ impl App {
             let clash_file_path = self.clash_dir.join(format!("{}.json", handle));
             std::fs::write(&clash_file_path, &content)?;
             println!("Saved clash {} as {}", &handle, &clash_file_path.display());
+            index.update(handle, &clash_file_path)?;
+            index.save(&self.index_file)?;
+        }
+        Ok(())
+    }
+
+    fn new_clash(&self, args: &ArgMatches) -> Result<()> {
+        let dir = args.get_one::<PathBuf>("DIR").context("Should have a directory")?;
+        let title = args.get_one::<String>("title").context("Should have a title")?;
+        let handle = PublicHandle::from_str(&format!("{:032x}", rand::random::<u128>()))?;
+        ClashProject::new(dir.to_owned()).scaffold(&handle, title)?;
+        println!("Created clash {} in {}", handle, dir.display());
+        println!("Edit the files and add the clash with `coctus pack {}`", dir.display());
+        Ok(())
+    }
+
+    fn pack(&self, args: &ArgMatches) -> Result<()> {
+        let dir = args.get_one::<PathBuf>("DIR").context("Should have a directory")?;
+        let clash = ClashProject::new(dir.to_owned()).pack()?;
+        let contents = serde_json::to_string_pretty(&clash)?;
+        match args.get_one::<PathBuf>("output") {
+            Some(path) if path.to_str() == Some("-") => println!("{}", contents),
+            Some(path) => {
+                std::fs::write(path, &contents).with_context(|| format!("Unable to write {:?}", path))?;
+                println!("Saved clash {} as {}", clash.public_handle(), path.display());
+            }
+            None => {
+                std::fs::create_dir_all(&self.clash_dir)?;
+                let handle = clash.public_handle();
+                let clash_file_path = self.clash_dir.join(format!("{}.json", handle));
+                std::fs::write(&clash_file_path, &contents)?;
+                println!("Saved clash {} as {}", handle, clash_file_path.display());
+                let mut index = self.clash_index()?;
+                index.update(handle, &clash_file_path)?;
+                index.save(&self.index_file)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn lint(&self, args: &ArgMatches) -> Result<()> {
+        let clash = match args.get_one::<PathBuf>("dir") {
+            Some(dir) => ClashProject::new(dir.to_owned()).pack()?,
+            None => {
+                let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+                    Some(h) => h.to_owned(),
+                    None => self.current_handle()?,
+                };
+                self.read_clash(&handle)?
+            }
+        };
+        let diagnostics = lint(&clash);
+        let handle = clash.public_handle();
+        if args.get_one::<String>("format").is_some() {
+            println!("{}", serde_json::to_string_pretty(&diagnostics)?);
+        } else if diagnostics.is_empty() {
+            println!("No problems found in clash {}", handle);
+        } else {
+            OutputStyle::from_env(false).print_diagnostics(&diagnostics);
+        }
+        if !diagnostics.is_empty() {
+            return Err(anyhow!("Found {} problem(s) in clash {}", diagnostics.len(), handle))
+        }
+        Ok(())
+    }
+
+    fn tui(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let clash = self.read_clash(&handle)?;
+
+        let lang = self.selected_language(args)?;
+        let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
+        let ostyle = OutputStyle::from_env(show_whitespace);
+
+        let run_tests = |clash: &Clash| run_all_testcases(args, &lang, clash);
+        let next_clash = || {
+            let handle = self.random_handle()?;
+            self.change_clash(&handle)?;
+            self.read_clash(&handle)
+        };
+
+        Tui::new(clash, &ostyle).run(run_tests, next_clash)
+    }
+
+    fn host(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.random_handle()?,
+        };
+        let clash_file = self.clash_dir.join(format!("{}.json", handle));
+        let clash_json = std::fs::read_to_string(&clash_file)
+            .with_context(|| format!("Unable to find clash with handle {}", handle))?;
+        let clash = self.read_clash(&handle)?;
+        let mode = match args.get_one::<String>("mode") {
+            Some(mode) => mode.to_owned(),
+            None => random_mode(&clash),
+        };
+        let minutes = *args.get_one::<u64>("minutes").unwrap_or(&15);
+        let port = *args.get_one::<u16>("port").unwrap_or(&DEFAULT_PORT);
+
+        let game = Game {
+            clash: clash_json,
+            mode: mode.clone(),
+            minutes,
+        };
+        let host = Host::bind(("0.0.0.0", port), game)?;
+        println!(
+            "Hosting \"{}\" ({} mode, {} minutes) on port {}",
+            clash.title(),
+            mode,
+            minutes,
+            host.local_addr()?.port()
+        );
+        println!("Players can join with: coctus join <address of this machine>:{}", port);
+        println!("Press Ctrl-C to stop hosting.");
+        host.serve(move |standings| {
+            println!();
+            OutputStyle::from_env(false).print_leaderboard(standings, &mode);
+        })
+    }
+
+    fn join(&self, args: &ArgMatches) -> Result<()> {
+        let addr = args.get_one::<String>("ADDR").context("Should have an address")?;
+        let addr = if addr.contains(':') {
+            addr.to_owned()
+        } else {
+            format!("{}:{}", addr, DEFAULT_PORT)
+        };
+        let name = match args.get_one::<String>("name") {
+            Some(name) => name.to_owned(),
+            None => std::env::var("USER").unwrap_or_else(|_| String::from("player")),
+        };
+
+        let (mut player, game) = Player::join(addr.as_str(), &name)?;
+        let clash: Clash = serde_json::from_str(&game.clash).context("The host sent an invalid clash")?;
+        let handle = clash.public_handle().clone();
+        std::fs::create_dir_all(&self.clash_dir)?;
+        std::fs::write(self.clash_dir.join(format!("{}.json", handle)), &game.clash)?;
+        self.change_clash(&handle)?;
+        self.print_changed_clash(&handle);
+        println!(
+            "Joined as {}: \"{}\" in {} mode, {} minutes. Good luck!",
+            name,
+            clash.title(),
+            game.mode,
+            game.minutes
+        );
+        println!("Press Enter to test and submit your solution, or type `quit` to leave.");
+
+        let mut sender = player.sender()?;
+        let mode = game.mode.clone();
+        std::thread::spawn(move || loop {
+            match player.receive() {
+                Ok(Some(Message::Leaderboard { standings })) => {
+                    println!();
+                    OutputStyle::from_env(false).print_leaderboard(&standings, &mode);
+                }
+                Ok(Some(Message::Error { message })) => println!("Host: {}", message),
+                Ok(Some(_)) => {}
+                Ok(None) | Err(_) => {
+                    println!("The host closed the game, type `quit` to leave.");
+                    break
+                }
+            }
+        });
+
+        let lang = self.selected_language(args)?;
+        for line in std::io::stdin().lines() {
+            if line?.trim() == "quit" {
+                break
+            }
+            let test_runs = match run_all_testcases(args, &lang, &clash) {
+                Ok(test_runs) => test_runs,
+                Err(error) => {
+                    println!("Error: {:#}", error);
+                    continue
+                }
+            };
+            let source_file = args.get_one::<PathBuf>("source").or(lang.source.as_ref());
+            let code_length = match source_file {
+                Some(source_file) => Some(self.record_code_length(args, &handle, source_file, false)?.0),
+                None if game.mode == "shortest" => {
+                    println!("Shortest mode needs the source file to count its code length (use --source)");
+                    continue
+                }
+                None => None,
+            };
+            let submission = Submission {
+                passed: test_runs.iter().filter(|test_run| test_run.is_success()).count(),
+                total: clash.testcases().len(),
+                code_length,
+            };
+            println!("Submitting {}/{} passed testcases...", submission.passed, submission.total);
+            sender.submit(submission)?;
+        }
+        Ok(())
+    }
+
+    /// Testcases of the clash followed by its custom testcases.
+    fn testcases_with_custom(&self, clash: &Clash, handle: &PublicHandle) -> Result<Vec<Testcase>> {
+        let custom_tests = CustomTests::load(&CustomTests::path(&self.clash_dir, handle))?;
+        let mut testcases = clash.testcases().to_owned();
+        testcases.extend(custom_tests.to_testcases(testcases.len()));
+        Ok(testcases)
+    }
+
+    fn check_inputs(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let clash = self.read_clash(&handle)?;
+        let stub_generator = self.stub_generator_from_args(args)?;
+
+        let ostyle = OutputStyle::from_env(false);
+        let mut num_invalid = 0;
+        for testcase in self.testcases_with_custom(&clash, &handle)? {
+            let title = ostyle.styled_testcase_title(&testcase);
+            match stub::check_input(&stub_generator, &testcase.test_in)? {
+                None => println!("{} {}", ostyle.success.paint("VALID"), title),
+                Some(mismatch) => {
+                    num_invalid += 1;
+                    println!("{} {}\n  {}", ostyle.failure.paint("INVALID"), title, mismatch);
+                }
+            }
+        }
+        if num_invalid > 0 {
+            return Err(anyhow!("{} testcase input(s) do not follow the stub generator", num_invalid))
+        }
+        Ok(())
+    }
+
+    /// The stub generator in --from-file, or else the one of the clash.
+    fn stub_generator_from_args(&self, args: &ArgMatches) -> Result<String> {
+        if let Some(path) = args.get_one::<PathBuf>("from-file") {
+            return std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))
+        }
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let clash = self.read_clash(&handle)?;
+        let stub_generator = clash
+            .stub_generator()
+            .with_context(|| format!("Clash {} provides no input stub generator", handle))?;
+        Ok(stub_generator.to_owned())
+    }
+
+    fn gen_input(&self, args: &ArgMatches) -> Result<()> {
+        let stub_generator = self.stub_generator_from_args(args)?;
+        let ranges = ranges_from_args(args);
+        let mut rng = StdRng::seed_from_u64(seed_from_args(args));
+        println!("{}", stub::generate_input(&stub_generator, &ranges, &mut rng)?);
+        Ok(())
+    }
+
+    fn stress(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        let clash = self.read_clash(&handle)?;
+        let stub_generator = self.stub_generator_from_args(args)?;
+
+        let lang = self.selected_language(args)?;
+        build_solution(args, &lang, None)?;
+        let mut run_command = run_command_from_args(args, &lang)?;
+        let mut reference_command =
+            command_from_argument(args.get_one::<String>("reference"))?.context("Empty --reference")?;
+        let timeout = timeout_from_args(args, &lang)?;
+        let limits = solution::ResourceLimits::default();
+        let checker = checker_from_args(args)?;
+
+        let ranges = ranges_from_args(args);
+        let seed = seed_from_args(args);
+        let mut rng = StdRng::seed_from_u64(seed);
+        let iterations = *args.get_one::<u64>("iterations").unwrap_or(&100);
+        let max_shrinks = *args.get_one::<u64>("max-shrinks").unwrap_or(&500) as usize;
+
+        let path = CustomTests::path(&self.clash_dir, &handle);
+        let mut custom_tests = CustomTests::load(&path)?;
+        let index = clash.testcases().len() + custom_tests.len() + 1;
+        let title = format!("Stress test (seed {})", seed);
+
+        // Runs both commands on `input`. Returns the run of the solution if
+        // its output differs from the one of the reference, or an error if
+        // the reference itself fails.
+        let mut find_mismatch = |input: &str| -> Result<Option<(Testcase, TestRun)>> {
+            let mut testcase = Testcase {
+                index,
+                title: title.clone(),
+                test_in: input.to_owned(),
+                test_out: String::new(),
+                is_validator: false,
+            };
+            let reference_run = solution::capture_output(input, &mut reference_command, &timeout, &limits);
+            testcase.test_out = match reference_run.result {
+                TestResult::Success { stdout, .. } => stdout,
+                result => {
+                    let mut msg = format!("The reference failed on this input:\n{}", input);
+                    if let Some(stderr) = result.stderr().filter(|stderr| !stderr.is_empty()) {
+                        msg.push_str(&format!("\nReference STDERR:\n{}", stderr.trim_end()));
+                    }
+                    return Err(anyhow!(msg))
+                }
+            };
+            let test_run = solution::run_testcase(&testcase, &mut run_command, &timeout, &limits, &*checker);
+            Ok((!test_run.is_success()).then_some((testcase, test_run)))
+        };
+
+        for iteration in 1..=iterations {
+            let generated = stub::generate_shrinkable_input(&stub_generator, &ranges, &mut rng)?;
+            if find_mismatch(&generated.input)?.is_none() {
+                continue
+            }
+            println!("The outputs differ on input #{}, shrinking it...", iteration);
+            // Smaller inputs that the reference fails on do not count
+            let still_fails = |input: &str| matches!(find_mismatch(input), Ok(Some(_)));
+            let shrunk = stub::shrink_input(&stub_generator, &ranges, generated, still_fails, max_shrinks)?;
+            let (testcase, test_run) = find_mismatch(&shrunk.input)?
+                .context("The shrunk input should still give different outputs")?;
+
+            let ostyle = OutputStyle::from_env(true);
+            ostyle.print_result(&testcase, &test_run);
+            custom_tests.add(testcase.title, testcase.test_in, testcase.test_out);
+            custom_tests.save(&path)?;
+            println!("Saved the input as custom testcase #{} of clash {}", index, handle);
+            return Err(anyhow!("The solution and the reference disagree"))
         }
+        println!("The outputs of {} inputs were the same", iterations);
+        Ok(())
+    }
+
+    fn addtest(&self, args: &ArgMatches) -> Result<()> {
+        let handle = match args.get_one::<PublicHandle>("PUBLIC_HANDLE") {
+            Some(h) => h.to_owned(),
+            None => self.current_handle()?,
+        };
+        // Make sure the clash exists before attaching testcases to it
+        let clash = self.read_clash(&handle)?;
+
+        let read_contents = |text_arg: &str, file_arg: &str| -> Result<String> {
+            if let Some(text) = args.get_one::<String>(text_arg) {
+                return Ok(text.to_owned())
+            }
+            let path = args.get_one::<PathBuf>(file_arg).context("Should have a file")?;
+            let mut contents = if path.to_str() == Some("-") {
+                let mut contents = String::new();
+                std::io::stdin().read_to_string(&mut contents)?;
+                contents
+            } else {
+                std::fs::read_to_string(path).with_context(|| format!("Unable to read {:?}", path))?
+            };
+            if contents.ends_with('\n') {
+                contents.pop();
+            }
+            Ok(contents)
+        };
+        let input = read_contents("input", "input-file")?;
+        let output = read_contents("output", "output-file")?;
+        let title = args.get_one::<String>("title").context("Should have a title")?.to_owned();
+
+        let path = CustomTests::path(&self.clash_dir, &handle);
+        let mut custom_tests = CustomTests::load(&path)?;
+        custom_tests.add(title, input, output);
+        custom_tests.save(&path)?;
+        let index = clash.testcases().len() + custom_tests.len();
+        println!("Added custom testcase #{} to clash {}", index, handle);
         Ok(())
     }
 
     fn showtests(&self, args: &ArgMatches) -> Result<()> {
         let handle = self.current_handle()?;
         let clash = self.read_clash(&handle)?;
-        let all_testcases = clash.testcases();
+        let num_clash_testcases = clash.testcases().len();
+        let all_testcases = self.testcases_with_custom(&clash, &handle)?;
 
         let show_whitespace = *args.get_one::<bool>("show-whitespace").unwrap_or(&false);
         let ostyle = OutputStyle::from_env(show_whitespace);
@@ -433,6 +2052,10 @@ impl App {
                 }
             };
 
+            if !is_selected_kind(testcase, args) || !is_selected_origin(testcase, num_clash_testcases, args) {
+                continue
+            }
+
             if !(only_in || only_out) {
                 let styled_title = ostyle.title.paint(format!("#{} {}", idx, testcase.title));
                 println!("{styled_title}");
@@ -482,10 +2105,15 @@ impl App {
         let lang_template_dir = self.stub_templates_dir.join(lang_arg);
         let stub_string = if lang_template_dir.is_dir() {
             let stub_config = StubConfig::read_from_dir(lang_template_dir)?;
-            stub::generate_from_config(stub_config, &stub_generator)?
+            stub::generate_from_config(stub_config, &stub_generator)
         } else {
-            stub::generate(lang_arg, &stub_generator)?
+            stub::generate(lang_arg, &stub_generator)
         };
+        // Point at the mistake in the stub generator
+        let stub_string = stub_string.map_err(|error| match error.downcast_ref::<StubParseError>() {
+            Some(parse_error) => anyhow!(parse_error.render(&stub_generator)),
+            None => error,
+        })?;
         println!("{stub_string}");
         Ok(())
     }
@@ -524,11 +2152,29 @@ fn main() -> Result<()> {
 
     match cli().get_matches().subcommand() {
         Some(("show", args)) => app.show(args),
+        Some(("list", args)) => app.list(args),
         Some(("next", args)) => app.next(args),
         Some(("status", args)) => app.status(args),
+        Some(("clash", args)) => app.clash(args),
+        Some(("history", args)) => app.history(args),
+        Some(("stats", args)) => app.stats(args),
         Some(("run", args)) => app.run(args),
+        Some(("bench", args)) => app.bench(args),
+        Some(("score", args)) => app.score(args),
+        Some(("save", args)) => app.save(args),
+        Some(("solutions", args)) => app.solutions(args),
         Some(("fetch", args)) => app.fetch(args),
+        Some(("new", args)) => app.new_clash(args),
+        Some(("pack", args)) => app.pack(args),
+        Some(("lint", args)) => app.lint(args),
+        Some(("check-inputs", args)) => app.check_inputs(args),
+        Some(("gen-input", args)) => app.gen_input(args),
+        Some(("stress", args)) => app.stress(args),
+        Some(("addtest", args)) => app.addtest(args),
         Some(("showtests", args)) => app.showtests(args),
+        Some(("tui", args)) => app.tui(args),
+        Some(("host", args)) => app.host(args),
+        Some(("join", args)) => app.join(args),
         Some(("json", args)) => app.json(args),
         Some(("generate-stub", args)) => app.generate_stub(args),
         Some(("generate-shell-completion", args)) => app.generate_completions(args),
@@ -1,22 +1,40 @@
+mod bench;
+mod checker;
+mod report;
+mod resource_limits;
 mod test_result;
 
-use std::io::Write;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
 use std::process::Command;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
+pub use bench::{bench_testcase, BenchStats};
+pub use checker::{
+    parse_checker, Checker, CommandChecker, ExactChecker, FloatChecker, TokenChecker, UnorderedLinesChecker,
+};
+pub use report::{ReportFormat, SuiteReport, TestReport};
+pub use resource_limits::ResourceLimits;
 use test_result::CommandExit;
-pub use test_result::TestResult;
+pub use test_result::{TestResult, TestRun};
 use wait_timeout::ChildExt;
 
 use crate::clash::Testcase;
 
+/// How often the memory usage of a running solution is sampled (on platforms
+/// where it is supported).
+const MEMORY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 /// Run a command against testcases one at a time.
 ///
 /// # Examples
 ///
 /// ```
 /// use clashlib::clash::Testcase;
-/// use clashlib::solution::lazy_run;
+/// use clashlib::solution::{lazy_run, ExactChecker, ResourceLimits};
 ///
 /// let testcases = [
 ///     Testcase {
@@ -29,25 +47,251 @@ use crate::clash::Testcase;
 /// ];
 /// let mut command = std::process::Command::new("cat");
 /// let timeout = std::time::Duration::from_secs(5);
+/// let limits = ResourceLimits::default();
 ///
-/// for (testcase, test_result) in lazy_run(&testcases, &mut command, &timeout) {
+/// for (testcase, test_run) in lazy_run(&testcases, &mut command, &timeout, &limits, &ExactChecker) {
 ///     assert_eq!(testcase.title, "Test #1");
-///     assert!(test_result.is_success());
+///     assert!(test_run.is_success());
 /// }
 /// ```
 pub fn lazy_run<'a>(
     testcases: impl IntoIterator<Item = &'a Testcase>,
     run_command: &'a mut Command,
     timeout: &'a Duration,
-) -> impl IntoIterator<Item = (&'a Testcase, TestResult)> {
+    limits: &'a ResourceLimits,
+    checker: &'a dyn Checker,
+) -> impl IntoIterator<Item = (&'a Testcase, TestRun)> {
     testcases.into_iter().map(|test| {
-        let result = run_testcase(test, run_command, timeout);
+        let result = run_testcase(test, run_command, timeout, limits, checker);
         (test, result)
     })
 }
 
-/// Run a command against a single testcase.
-pub fn run_testcase(testcase: &Testcase, run_command: &mut Command, timeout: &Duration) -> TestResult {
+/// Run a command against testcases using `jobs` worker threads.
+///
+/// Results are yielded in the same order as `testcases` regardless of the
+/// order in which the workers finish them. Dropping the returned iterator
+/// stops the workers from starting any new testcases, so breaking out of the
+/// loop early works the same way as with [lazy_run] (except that up to `jobs`
+/// testcases may already be running at that point).
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::Testcase;
+/// use clashlib::solution::{parallel_run, ExactChecker, ResourceLimits};
+///
+/// let testcases: Vec<Testcase> = (1..=4)
+///     .map(|index| Testcase {
+///         index,
+///         title: format!("Test #{index}"),
+///         test_in: index.to_string(),
+///         test_out: index.to_string(),
+///         is_validator: false,
+///     })
+///     .collect();
+/// let command = std::process::Command::new("cat");
+/// let timeout = std::time::Duration::from_secs(5);
+///
+/// let limits = ResourceLimits::default();
+/// let suite_run = parallel_run(&testcases, &command, &timeout, &limits, &ExactChecker, 2);
+/// for (expected_index, (testcase, test_run)) in (1..).zip(suite_run) {
+///     assert_eq!(testcase.index, expected_index);
+///     assert!(test_run.is_success());
+/// }
+/// ```
+pub fn parallel_run<'a>(
+    testcases: impl IntoIterator<Item = &'a Testcase>,
+    run_command: &Command,
+    timeout: &Duration,
+    limits: &ResourceLimits,
+    checker: &(dyn Checker + 'static),
+    jobs: usize,
+) -> ParallelRun<'a> {
+    let testcases: Vec<&'a Testcase> = testcases.into_iter().collect();
+    let owned_testcases: Arc<Vec<Testcase>> = Arc::new(testcases.iter().map(|&t| t.clone()).collect());
+    let next_testcase = Arc::new(AtomicUsize::new(0));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+
+    let num_workers = jobs.clamp(1, testcases.len().max(1));
+    let workers = (0..num_workers)
+        .map(|_| {
+            let mut command = clone_command(run_command);
+            let timeout = *timeout;
+            let limits = *limits;
+            let checker = dyn_clone::clone_box(checker);
+            let testcases = Arc::clone(&owned_testcases);
+            let next_testcase = Arc::clone(&next_testcase);
+            let cancelled = Arc::clone(&cancelled);
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                while !cancelled.load(Ordering::Relaxed) {
+                    let idx = next_testcase.fetch_add(1, Ordering::Relaxed);
+                    let Some(testcase) = testcases.get(idx) else { break };
+                    let result = run_testcase(testcase, &mut command, &timeout, &limits, checker.as_ref());
+                    if sender.send((idx, result)).is_err() {
+                        break
+                    }
+                }
+            })
+        })
+        .collect();
+
+    ParallelRun {
+        testcases,
+        next_idx: 0,
+        finished: BTreeMap::new(),
+        receiver,
+        cancelled,
+        workers,
+    }
+}
+
+/// Iterator returned by [parallel_run].
+pub struct ParallelRun<'a> {
+    testcases: Vec<&'a Testcase>,
+    next_idx: usize,
+    // Results that arrived before the results of the testcases preceding them
+    finished: BTreeMap<usize, TestRun>,
+    receiver: mpsc::Receiver<(usize, TestRun)>,
+    cancelled: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl<'a> Iterator for ParallelRun<'a> {
+    type Item = (&'a Testcase, TestRun);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let testcase = *self.testcases.get(self.next_idx)?;
+        let result = loop {
+            if let Some(result) = self.finished.remove(&self.next_idx) {
+                break result
+            }
+            // recv only fails once every worker has exited. Workers only exit
+            // early if they panic, so the testcases that are left were lost.
+            match self.receiver.recv() {
+                Ok((idx, result)) => self.finished.insert(idx, result),
+                Err(_) => {
+                    break TestRun {
+                        result: TestResult::UnableToRun {
+                            error_msg: String::from("The worker running this testcase panicked"),
+                        },
+                        duration: Duration::ZERO,
+                        peak_memory: None,
+                    }
+                }
+            };
+        };
+        self.next_idx += 1;
+        Some((testcase, result))
+    }
+}
+
+impl Drop for ParallelRun<'_> {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        // Wait for the testcases that are still running so that no child
+        // processes outlive the run.
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// `Command` does not implement `Clone`, but each worker of [parallel_run]
+/// needs one of its own.
+fn clone_command(command: &Command) -> Command {
+    let mut clone = Command::new(command.get_program());
+    clone.args(command.get_args());
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => clone.env(key, value),
+            None => clone.env_remove(key),
+        };
+    }
+    if let Some(dir) = command.get_current_dir() {
+        clone.current_dir(dir);
+    }
+    clone
+}
+
+/// Number of characters in `source` as counted by CodinGame in shortest mode.
+///
+/// Characters are counted the way JavaScript's `String.length` counts them
+/// (in UTF-16 code units), after normalizing line endings. A trailing newline
+/// is not counted since the CodinGame editor does not add one.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::solution::code_length;
+///
+/// assert_eq!(code_length("print(1)\r\n"), 8);
+/// assert_eq!(code_length("a\r\nb"), 3);
+/// ```
+pub fn code_length(source: &str) -> usize {
+    source.replace("\r\n", "\n").trim_end_matches('\n').encode_utf16().count()
+}
+
+/// Run a command against a single testcase, using `checker` to decide if its
+/// output is correct.
+pub fn run_testcase(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    timeout: &Duration,
+    limits: &ResourceLimits,
+    checker: &dyn Checker,
+) -> TestRun {
+    run_input(&testcase.test_in, run_command, timeout, limits, |stdout, stderr, exit_status| {
+        TestResult::from_output(testcase, checker, stdout, stderr, exit_status)
+    })
+}
+
+/// Run a command on `input` and capture its output without checking it. The
+/// result is a [TestResult::Success] with the output if the command exits
+/// normally, and the failure that stopped it otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::solution::{capture_output, ResourceLimits, TestResult};
+///
+/// let mut command = std::process::Command::new("rev");
+/// let timeout = std::time::Duration::from_secs(5);
+/// let test_run = capture_output("abc", &mut command, &timeout, &ResourceLimits::default());
+/// assert!(matches!(test_run.result, TestResult::Success { stdout, .. } if stdout == "cba"));
+/// ```
+pub fn capture_output(
+    input: &str,
+    run_command: &mut Command,
+    timeout: &Duration,
+    limits: &ResourceLimits,
+) -> TestRun {
+    run_input(input, run_command, timeout, limits, TestResult::from_exit)
+}
+
+/// Runs a command on `input`. `into_result` turns its output and the way it
+/// exited into the result of the run.
+fn run_input(
+    input: &str,
+    run_command: &mut Command,
+    timeout: &Duration,
+    limits: &ResourceLimits,
+    into_result: impl FnOnce(Vec<u8>, Vec<u8>, CommandExit) -> TestResult,
+) -> TestRun {
+    // pre_exec hooks can not be removed from a command, so the limits are
+    // applied to a copy instead of accumulating on the caller's command.
+    let mut limited_command;
+    let run_command = if limits.has_rlimits() {
+        limited_command = clone_command(run_command);
+        limits.apply_rlimits(&mut limited_command);
+        &mut limited_command
+    } else {
+        run_command
+    };
+
+    let start = Instant::now();
     let mut run = match run_command
         .stdin(std::process::Stdio::piped())
         .stdout(std::process::Stdio::piped())
@@ -58,35 +302,113 @@ pub fn run_testcase(testcase: &Testcase, run_command: &mut Command, timeout: &Du
         Err(error) => {
             let program = run_command.get_program().to_str().unwrap_or("Unable to run command");
             let error_msg = format!("{}: {}", program, error);
-            return TestResult::UnableToRun { error_msg }
+            return TestRun {
+                result: TestResult::UnableToRun { error_msg },
+                duration: Duration::ZERO,
+                peak_memory: None,
+            }
         }
     };
 
-    run.stdin
-        .as_mut()
-        .expect("STDIN of child process should be captured")
-        .write_all(testcase.test_in.as_bytes())
-        .expect("STDIN of child process should be writable");
+    // STDOUT and STDERR are read in separate threads so that a solution
+    // printing a lot of output can not block forever on a full pipe.
+    let stdout = run.stdout.take().expect("STDOUT of child process should be captured");
+    let stderr = run.stderr.take().expect("STDERR of child process should be captured");
+    let stdout_reader = read_limited(stdout, limits.output);
+    let stderr_reader = read_truncated(stderr, limits.output);
 
-    let timed_out = run
-        .wait_timeout(*timeout)
-        .expect("Process should be able to wait for execution")
-        .is_none();
+    let mut stdin = run.stdin.take().expect("STDIN of child process should be captured");
+    let test_in = input.to_owned();
+    let stdin_writer = std::thread::spawn(move || {
+        // The solution is free to exit without reading all of its input
+        let _ = stdin.write_all(test_in.as_bytes());
+    });
 
-    if timed_out {
-        run.kill().expect("Process should have been killed");
-    }
+    let mut usage = resource_limits::Usage::default();
+    let exit_status = loop {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        let wait_time = if resource_limits::CAN_MEASURE_USAGE {
+            remaining.min(MEMORY_POLL_INTERVAL)
+        } else {
+            remaining
+        };
+
+        let status = run.wait_timeout(wait_time).expect("Process should be able to wait for execution");
+        if let Some(status) = status {
+            if limits.exceeded_cpu_time(&status, &usage) {
+                break CommandExit::Timeout
+            } else if status.success() {
+                break CommandExit::Ok
+            } else if limits.exceeded_memory(&usage) {
+                break CommandExit::MemoryLimitExceeded
+            } else {
+                break CommandExit::Error
+            }
+        }
+
+        usage.measure(run.id());
+
+        if start.elapsed() >= *timeout {
+            run.kill().expect("Process should have been killed");
+            break CommandExit::Timeout
+        }
+    };
 
-    let output = run.wait_with_output().expect("Process should allow waiting for its execution");
+    run.wait().expect("Process should allow waiting for its execution");
+    let duration = start.elapsed();
+    let _ = stdin_writer.join();
+    let (stdout, output_limit_exceeded) = stdout_reader.join().expect("STDOUT reader should not panic");
+    let stderr = stderr_reader.join().expect("STDERR reader should not panic");
 
-    let exit_status = if timed_out {
-        CommandExit::Timeout
-    } else if output.status.success() {
-        CommandExit::Ok
+    let exit_status = if output_limit_exceeded {
+        CommandExit::OutputLimitExceeded
     } else {
-        CommandExit::Error
+        exit_status
     };
-    TestResult::from_output(&testcase.test_out, output.stdout, output.stderr, exit_status)
+    TestRun {
+        result: into_result(stdout, stderr, exit_status),
+        duration,
+        peak_memory: usage.peak_memory,
+    }
+}
+
+/// Reads everything from `pipe` until it reaches `limit` bytes. Returns the
+/// bytes that were read and whether the limit was exceeded. Once the limit is
+/// exceeded the pipe is closed, which makes further writes fail in the child
+/// process.
+fn read_limited(mut pipe: impl Read + Send + 'static, limit: Option<u64>) -> JoinHandle<(Vec<u8>, bool)> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        match limit {
+            Some(limit) => {
+                let _ = pipe.by_ref().take(limit.saturating_add(1)).read_to_end(&mut buf);
+                let exceeded = buf.len() as u64 > limit;
+                buf.truncate(limit as usize);
+                (buf, exceeded)
+            }
+            None => {
+                let _ = pipe.read_to_end(&mut buf);
+                (buf, false)
+            }
+        }
+    })
+}
+
+/// Reads everything from `pipe` but only keeps the first `limit` bytes.
+fn read_truncated(mut pipe: impl Read + Send + 'static, limit: Option<u64>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        match limit {
+            Some(limit) => {
+                let _ = pipe.by_ref().take(limit).read_to_end(&mut buf);
+                let _ = std::io::copy(&mut pipe, &mut std::io::sink());
+            }
+            None => {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+        }
+        buf
+    })
 }
 
 #[cfg(test)]
@@ -100,9 +422,10 @@ mod tests {
         run_cmd.arg("X");
         run_cmd.arg("b");
         let timeout = Duration::from_secs(1);
-        assert!(lazy_run(clash.testcases(), &mut run_cmd, &timeout)
+        let limits = ResourceLimits::default();
+        assert!(lazy_run(clash.testcases(), &mut run_cmd, &timeout, &limits, &ExactChecker)
             .into_iter()
-            .all(|(_, test_result)| test_result.is_success()))
+            .all(|(_, test_run)| test_run.is_success()))
     }
 
     #[test]
@@ -110,8 +433,165 @@ mod tests {
         let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
         let timeout = Duration::from_secs(1);
         let mut run_cmd = Command::new("cat");
-        assert!(lazy_run(clash.testcases(), &mut run_cmd, &timeout)
+        let limits = ResourceLimits::default();
+        assert!(lazy_run(clash.testcases(), &mut run_cmd, &timeout, &limits, &ExactChecker)
             .into_iter()
-            .all(|(_, test_result)| !test_result.is_success()))
+            .all(|(_, test_run)| !test_run.is_success()))
+    }
+
+    #[test]
+    fn test_parallel_passing_solution() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let mut run_cmd = Command::new("tr");
+        run_cmd.arg("X");
+        run_cmd.arg("b");
+        let timeout = Duration::from_secs(1);
+        let limits = ResourceLimits::default();
+        assert!(parallel_run(clash.testcases(), &run_cmd, &timeout, &limits, &ExactChecker, 4)
+            .all(|(_, test_run)| test_run.is_success()))
+    }
+
+    #[test]
+    fn test_parallel_run_preserves_order() {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let run_cmd = Command::new("cat");
+        let timeout = Duration::from_secs(1);
+        let limits = ResourceLimits::default();
+        let indices: Vec<usize> =
+            parallel_run(clash.testcases(), &run_cmd, &timeout, &limits, &ExactChecker, 3)
+                .map(|(testcase, _)| testcase.index)
+                .collect();
+        let expected: Vec<usize> = clash.testcases().iter().map(|testcase| testcase.index).collect();
+        assert_eq!(indices, expected);
+    }
+
+    #[test]
+    fn test_parallel_run_reports_testcases_lost_to_panics() {
+        #[derive(Debug, Clone)]
+        struct PanickingChecker;
+
+        impl Checker for PanickingChecker {
+            fn check(&self, _testcase: &Testcase, _output: &str) -> bool {
+                panic!("checker failure")
+            }
+        }
+
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let run_cmd = Command::new("cat");
+        let timeout = Duration::from_secs(1);
+        let limits = ResourceLimits::default();
+        let results: Vec<TestRun> =
+            parallel_run(clash.testcases(), &run_cmd, &timeout, &limits, &PanickingChecker, 2)
+                .map(|(_, test_run)| test_run)
+                .collect();
+        assert_eq!(results.len(), clash.testcases().len());
+        assert!(results
+            .iter()
+            .all(|test_run| matches!(test_run.result, TestResult::UnableToRun { .. })));
+    }
+
+    #[test]
+    fn test_output_limit_exceeded() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Test #1"),
+            test_in: String::new(),
+            test_out: String::from("y"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("yes");
+        let timeout = Duration::from_secs(5);
+        let limits = ResourceLimits {
+            output: Some(1000),
+            ..Default::default()
+        };
+        match run_testcase(&testcase, &mut run_cmd, &timeout, &limits, &ExactChecker).result {
+            TestResult::OutputLimitExceeded { stdout, .. } => assert!(stdout.len() <= 1000),
+            other => panic!("expected TestResult::OutputLimitExceeded but found {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_memory_limit_exceeded() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Test #1"),
+            test_in: String::new(),
+            test_out: String::from("y"),
+            is_validator: false,
+        };
+        // The shell holds 42 MB for a while (so that it gets measured), then
+        // tries to hold 30 MB more
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args([
+            "-c",
+            "x=$(head -c 30000000 /dev/zero | tr '\\0' a); y=$(head -c 12000000 /dev/zero | tr '\\0' a); \
+             sleep 0.2; z=$(head -c 30000000 /dev/zero | tr '\\0' a); echo y",
+        ]);
+        let timeout = Duration::from_secs(5);
+        let limits = ResourceLimits {
+            memory: Some(76 * 1024 * 1024),
+            ..Default::default()
+        };
+        match run_testcase(&testcase, &mut run_cmd, &timeout, &limits, &ExactChecker).result {
+            TestResult::MemoryLimitExceeded { .. } => {}
+            other => panic!("expected TestResult::MemoryLimitExceeded but found {:?}", other),
+        }
+        let unlimited = ResourceLimits::default();
+        assert!(run_testcase(&testcase, &mut run_cmd, &timeout, &unlimited, &ExactChecker).is_success());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_failure_without_memory_growth_is_a_runtime_error() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Test #1"),
+            test_in: String::new(),
+            test_out: String::from("y"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "kill -KILL $$"]);
+        let timeout = Duration::from_secs(5);
+        let limits = ResourceLimits {
+            memory: Some(76 * 1024 * 1024),
+            cpu_time: Some(1),
+            ..Default::default()
+        };
+        match run_testcase(&testcase, &mut run_cmd, &timeout, &limits, &ExactChecker).result {
+            TestResult::RuntimeError { .. } => {}
+            other => panic!("expected TestResult::RuntimeError but found {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_cpu_limit_is_a_timeout_even_if_sigxcpu_is_ignored() {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Test #1"),
+            test_in: String::new(),
+            test_out: String::from("y"),
+            is_validator: false,
+        };
+        let mut run_cmd = Command::new("sh");
+        run_cmd.args(["-c", "trap '' XCPU; while :; do :; done"]);
+        let timeout = Duration::from_secs(10);
+        let limits = ResourceLimits {
+            cpu_time: Some(1),
+            ..Default::default()
+        };
+        match run_testcase(&testcase, &mut run_cmd, &timeout, &limits, &ExactChecker).result {
+            TestResult::Timeout { .. } => {}
+            other => panic!("expected TestResult::Timeout but found {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_code_length_counts_utf16_code_units() {
+        assert_eq!(code_length("é"), 1);
+        assert_eq!(code_length("🦀"), 2);
     }
 }
@@ -0,0 +1,86 @@
+use std::process::Command;
+use std::time::Duration;
+
+use super::{run_testcase, Checker, ResourceLimits};
+use crate::clash::Testcase;
+
+/// Timing statistics from running a solution against the same testcase
+/// several times.
+#[derive(Debug, Clone)]
+pub struct BenchStats {
+    pub runs: usize,
+    /// How many of the runs produced the expected output.
+    pub passed: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub max: Duration,
+    /// Highest peak memory usage (in bytes) over all runs, if it could be
+    /// measured.
+    pub peak_memory: Option<u64>,
+}
+
+impl BenchStats {
+    fn from_runs(durations: &mut [Duration], passed: usize, peak_memory: Option<u64>) -> Self {
+        durations.sort_unstable();
+        let mid = durations.len() / 2;
+        let median = match durations.len() {
+            0 => Duration::ZERO,
+            len if len % 2 == 0 => (durations[mid - 1] + durations[mid]) / 2,
+            _ => durations[mid],
+        };
+        BenchStats {
+            runs: durations.len(),
+            passed,
+            min: durations.first().copied().unwrap_or_default(),
+            median,
+            max: durations.last().copied().unwrap_or_default(),
+            peak_memory,
+        }
+    }
+}
+
+/// Run a command against a single testcase `runs` times in a row.
+pub fn bench_testcase(
+    testcase: &Testcase,
+    run_command: &mut Command,
+    timeout: &Duration,
+    limits: &ResourceLimits,
+    checker: &dyn Checker,
+    runs: usize,
+) -> BenchStats {
+    let mut durations = Vec::with_capacity(runs);
+    let mut passed = 0;
+    let mut peak_memory = None;
+
+    for _ in 0..runs {
+        let test_run = run_testcase(testcase, run_command, timeout, limits, checker);
+        if test_run.is_success() {
+            passed += 1;
+        }
+        durations.push(test_run.duration);
+        peak_memory = peak_memory.max(test_run.peak_memory);
+    }
+
+    BenchStats::from_runs(&mut durations, passed, peak_memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_of_odd_number_of_runs() {
+        let mut durations = [5, 1, 3].map(Duration::from_millis);
+        let stats = BenchStats::from_runs(&mut durations, 3, None);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(3));
+        assert_eq!(stats.max, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn stats_of_even_number_of_runs() {
+        let mut durations = [8, 2, 4, 6].map(Duration::from_millis);
+        let stats = BenchStats::from_runs(&mut durations, 4, None);
+        assert_eq!(stats.median, Duration::from_millis(5));
+    }
+}
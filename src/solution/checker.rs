@@ -0,0 +1,258 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, Context, Result};
+use dyn_clone::DynClone;
+
+use crate::clash::Testcase;
+
+/// Decides whether the output of a solution is correct for a testcase.
+///
+/// `output` has already been normalized: line endings are converted to `\n`
+/// and trailing whitespace is trimmed.
+pub trait Checker: std::fmt::Debug + DynClone + Send + Sync {
+    fn check(&self, testcase: &Testcase, output: &str) -> bool;
+}
+
+dyn_clone::clone_trait_object!(Checker);
+
+/// Output must be identical to the expected output (ignoring trailing
+/// whitespace at the end of the output). This is how CodinGame checks
+/// solutions.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExactChecker;
+
+impl Checker for ExactChecker {
+    fn check(&self, testcase: &Testcase, output: &str) -> bool {
+        output == testcase.test_out.trim_end()
+    }
+}
+
+/// Output must contain the same whitespace separated tokens as the expected
+/// output. The amount and kind of whitespace between them does not matter.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokenChecker;
+
+impl Checker for TokenChecker {
+    fn check(&self, testcase: &Testcase, output: &str) -> bool {
+        output.split_whitespace().eq(testcase.test_out.split_whitespace())
+    }
+}
+
+/// Compares tokens like [TokenChecker], except that tokens that are numbers
+/// on both sides are equal if they differ by at most `epsilon`, either in
+/// absolute or relative terms.
+#[derive(Debug, Clone, Copy)]
+pub struct FloatChecker {
+    pub epsilon: f64,
+}
+
+impl Default for FloatChecker {
+    fn default() -> Self {
+        FloatChecker { epsilon: 1e-6 }
+    }
+}
+
+impl FloatChecker {
+    fn tokens_match(&self, actual: &str, expected: &str) -> bool {
+        match (actual.parse::<f64>(), expected.parse::<f64>()) {
+            (Ok(actual), Ok(expected)) if !actual.is_finite() || !expected.is_finite() => {
+                // inf - inf and anything involving NaN is NaN, which is never
+                // within epsilon
+                actual == expected || actual.is_nan() && expected.is_nan()
+            }
+            (Ok(actual), Ok(expected)) => {
+                let diff = (actual - expected).abs();
+                diff <= self.epsilon || diff <= self.epsilon * expected.abs()
+            }
+            _ => actual == expected,
+        }
+    }
+}
+
+impl Checker for FloatChecker {
+    fn check(&self, testcase: &Testcase, output: &str) -> bool {
+        let mut actual = output.split_whitespace();
+        let mut expected = testcase.test_out.split_whitespace();
+        loop {
+            match (actual.next(), expected.next()) {
+                (None, None) => return true,
+                (Some(a), Some(e)) if self.tokens_match(a, e) => continue,
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// Output must contain the same lines as the expected output, in any order.
+/// Trailing whitespace of each line is ignored.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UnorderedLinesChecker;
+
+impl Checker for UnorderedLinesChecker {
+    fn check(&self, testcase: &Testcase, output: &str) -> bool {
+        sorted_lines(output) == sorted_lines(&testcase.test_out)
+    }
+}
+
+fn sorted_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.trim_end().lines().map(str::trim_end).collect();
+    lines.sort_unstable();
+    lines
+}
+
+/// Lets an external program decide. The program is called with the paths of
+/// three files as its last arguments: the testcase input, the output of the
+/// solution and the expected output. The output is correct if the program
+/// exits successfully.
+///
+/// If the program can not be run at all, every output is considered wrong.
+#[derive(Debug, Clone)]
+pub struct CommandChecker {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandChecker {
+    /// Create a checker from a command line like `"python3 check.py"`.
+    pub fn new(command: &str) -> Result<Self> {
+        let words = shlex::split(command).ok_or_else(|| anyhow!("Invalid checker command"))?;
+        let mut words = words.into_iter();
+        let program = words.next().context("Checker command is empty")?;
+        Ok(CommandChecker {
+            program,
+            args: words.collect(),
+        })
+    }
+
+    fn run(&self, files: &[PathBuf; 3]) -> bool {
+        Command::new(&self.program)
+            .args(&self.args)
+            .args(files)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+}
+
+impl Checker for CommandChecker {
+    fn check(&self, testcase: &Testcase, output: &str) -> bool {
+        // Checkers may run concurrently (see `parallel_run`), so every call
+        // gets its own set of files.
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let prefix = format!("coctus-checker-{}-{}", std::process::id(), id);
+        let files = ["input", "output", "expected"]
+            .map(|name| std::env::temp_dir().join(format!("{}-{}", prefix, name)));
+
+        let written = std::fs::write(&files[0], &testcase.test_in).is_ok()
+            && std::fs::write(&files[1], output).is_ok()
+            && std::fs::write(&files[2], &testcase.test_out).is_ok();
+        let accepted = written && self.run(&files);
+
+        for file in &files {
+            let _ = std::fs::remove_file(file);
+        }
+        accepted
+    }
+}
+
+/// Create a checker from its name as given on the command line:
+///
+/// - `exact`
+/// - `tokens`
+/// - `float` or `float:EPSILON` (e.g. `float:1e-4`)
+/// - `unordered-lines`
+/// - `command:COMMAND` (e.g. `command:python3 check.py`), see [CommandChecker]
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::solution::parse_checker;
+///
+/// assert!(parse_checker("float:0.001").is_ok());
+/// assert!(parse_checker("float:small").is_err());
+/// assert!(parse_checker("float:-1").is_err());
+/// assert!(parse_checker("fuzzy").is_err());
+/// ```
+pub fn parse_checker(spec: &str) -> Result<Box<dyn Checker>> {
+    let (name, param) = match spec.split_once(':') {
+        Some((name, param)) => (name, Some(param)),
+        None => (spec, None),
+    };
+    match (name, param) {
+        ("exact", None) => Ok(Box::new(ExactChecker)),
+        ("tokens", None) => Ok(Box::new(TokenChecker)),
+        ("float", None) => Ok(Box::<FloatChecker>::default()),
+        ("float", Some(epsilon)) => {
+            let epsilon = f64::from_str(epsilon).with_context(|| format!("Invalid epsilon {:?}", epsilon))?;
+            if epsilon < 0.0 || epsilon.is_nan() {
+                return Err(anyhow!("Epsilon must not be negative, got {}", epsilon))
+            }
+            Ok(Box::new(FloatChecker { epsilon }))
+        }
+        ("unordered-lines", None) => Ok(Box::new(UnorderedLinesChecker)),
+        ("command", Some(command)) => Ok(Box::new(CommandChecker::new(command)?)),
+        _ => Err(anyhow!(
+            "Unknown checker {:?} (expected exact, tokens, float[:EPSILON], unordered-lines or command:COMMAND)",
+            spec
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn testcase(test_out: &str) -> Testcase {
+        Testcase {
+            index: 1,
+            title: String::from("Test #1"),
+            test_in: String::from("in"),
+            test_out: test_out.to_string(),
+            is_validator: false,
+        }
+    }
+
+    #[test]
+    fn token_checker_ignores_whitespace() {
+        assert!(TokenChecker.check(&testcase("1 2\n3"), "1  2 3"));
+        assert!(!TokenChecker.check(&testcase("1 2 3"), "1 2"));
+        assert!(!ExactChecker.check(&testcase("1 2\n3"), "1  2 3"));
+    }
+
+    #[test]
+    fn float_checker_allows_epsilon() {
+        let checker = FloatChecker { epsilon: 1e-3 };
+        assert!(checker.check(&testcase("3.1416 yes"), "3.14159 yes"));
+        assert!(!checker.check(&testcase("3.1416 yes"), "3.13 yes"));
+        assert!(!checker.check(&testcase("3.1416 yes"), "3.1416 no"));
+        assert!(checker.check(&testcase("1000000"), "1000000.5"));
+    }
+
+    #[test]
+    fn float_checker_compares_non_finite_numbers() {
+        let checker = FloatChecker::default();
+        assert!(checker.check(&testcase("inf -inf NaN"), "inf -inf NaN"));
+        assert!(!checker.check(&testcase("inf"), "-inf"));
+        assert!(!checker.check(&testcase("inf"), "1e300"));
+        assert!(!checker.check(&testcase("NaN"), "0"));
+    }
+
+    #[test]
+    fn unordered_lines_checker_ignores_order() {
+        assert!(UnorderedLinesChecker.check(&testcase("a\nb\nc\n"), "c\na\nb"));
+        assert!(!UnorderedLinesChecker.check(&testcase("a\nb\nb"), "a\na\nb"));
+    }
+
+    #[test]
+    fn command_checker_uses_exit_status() {
+        let checker = CommandChecker::new(r#"sh -c 'cmp -s "$2" "$3"' checker"#).unwrap();
+        assert!(checker.check(&testcase("in"), "in"));
+        assert!(!checker.check(&testcase("out"), "in"));
+    }
+}
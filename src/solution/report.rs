@@ -0,0 +1,348 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use super::{TestResult, TestRun};
+use crate::clash::Testcase;
+
+/// Machine readable formats that a [SuiteReport] can be rendered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+    Tap,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ReportFormat::Json),
+            "junit" => Ok(ReportFormat::Junit),
+            "tap" => Ok(ReportFormat::Tap),
+            other => Err(anyhow!("unknown report format {:?} (expected json, junit or tap)", other)),
+        }
+    }
+}
+
+/// Outcome of a single testcase in a [SuiteReport].
+#[derive(Debug, Clone, Serialize)]
+pub struct TestReport {
+    pub index: usize,
+    pub title: String,
+    pub is_validator: bool,
+    /// `None` if the testcase was not run, for example because an earlier
+    /// testcase failed. The fields of [TestRun] are flattened into the
+    /// report when serialized.
+    #[serde(flatten)]
+    pub run: Option<TestRun>,
+}
+
+impl TestReport {
+    pub fn is_success(&self) -> bool {
+        self.run.as_ref().is_some_and(TestRun::is_success)
+    }
+}
+
+/// Results of running a solution against a selection of testcases, in a form
+/// that can be consumed by other tools.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::clash::Testcase;
+/// use clashlib::solution::{lazy_run, ExactChecker, ReportFormat, ResourceLimits, SuiteReport};
+///
+/// let testcases = [
+///     Testcase {
+///         index: 1,
+///         title: String::from("Test #1"),
+///         test_in: String::from("hey"),
+///         test_out: String::from("hey"),
+///         is_validator: false,
+///     }
+/// ];
+/// let mut command = std::process::Command::new("cat");
+/// let timeout = std::time::Duration::from_secs(5);
+/// let limits = ResourceLimits::default();
+///
+/// let mut report = SuiteReport::new("example", &testcases);
+/// for (testcase, test_run) in lazy_run(&testcases, &mut command, &timeout, &limits, &ExactChecker) {
+///     report.record(testcase, test_run);
+/// }
+/// assert!(report.all_passed());
+/// assert!(report.render(ReportFormat::Tap).contains("ok 1 - Test \\#1"));
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteReport {
+    /// Name of the suite, usually the handle of the clash.
+    pub name: String,
+    pub testcases: Vec<TestReport>,
+}
+
+impl SuiteReport {
+    /// Creates a report in which none of the `testcases` have been run yet.
+    pub fn new<'a>(name: impl Into<String>, testcases: impl IntoIterator<Item = &'a Testcase>) -> Self {
+        let testcases = testcases
+            .into_iter()
+            .map(|testcase| TestReport {
+                index: testcase.index,
+                title: testcase.title.clone(),
+                is_validator: testcase.is_validator,
+                run: None,
+            })
+            .collect();
+        SuiteReport {
+            name: name.into(),
+            testcases,
+        }
+    }
+
+    /// Records the outcome of running `testcase`.
+    pub fn record(&mut self, testcase: &Testcase, run: TestRun) {
+        let not_yet_run = self
+            .testcases
+            .iter_mut()
+            .find(|test| test.index == testcase.index && test.run.is_none());
+        if let Some(test) = not_yet_run {
+            test.run = Some(run);
+        }
+    }
+
+    pub fn num_passed(&self) -> usize {
+        self.testcases.iter().filter(|test| test.is_success()).count()
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.testcases.iter().all(TestReport::is_success)
+    }
+
+    /// Number of passed and total testcases that are not validators.
+    pub fn tests_passed(&self) -> (usize, usize) {
+        self.count_passed(false)
+    }
+
+    /// Number of passed and total validators.
+    pub fn validators_passed(&self) -> (usize, usize) {
+        self.count_passed(true)
+    }
+
+    fn count_passed(&self, validators: bool) -> (usize, usize) {
+        let selected: Vec<&TestReport> =
+            self.testcases.iter().filter(|test| test.is_validator == validators).collect();
+        let passed = selected.iter().filter(|test| test.is_success()).count();
+        (passed, selected.len())
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Json => self.to_json(),
+            ReportFormat::Junit => self.to_junit(),
+            ReportFormat::Tap => self.to_tap(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("SuiteReport should be serializable as JSON")
+    }
+
+    /// Renders the report as JUnit XML. Wrong output is reported as a
+    /// `<failure>` and every other unsuccessful result as an `<error>`.
+    pub fn to_junit(&self) -> String {
+        let runs = || self.testcases.iter().filter_map(|test| test.run.as_ref());
+        let failures = runs().filter(|run| matches!(run.result, TestResult::WrongOutput { .. })).count();
+        let errors = runs().filter(|run| !run.is_success()).count() - failures;
+        let skipped = self.testcases.len() - runs().count();
+        let total_time: f64 = runs().map(|run| run.duration.as_secs_f64()).sum();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(&self.name),
+            self.testcases.len(),
+            failures,
+            errors,
+            skipped,
+            total_time,
+        ));
+
+        for test in &self.testcases {
+            let classname = if test.is_validator { "validators" } else { "tests" };
+            let time = test.run.as_ref().map_or(0.0, |run| run.duration.as_secs_f64());
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape_xml(&format!("#{} {}", test.index, test.title)),
+                classname,
+                time,
+            ));
+
+            let Some(run) = &test.run else {
+                xml.push_str(">\n      <skipped/>\n    </testcase>\n");
+                continue
+            };
+            xml.push_str(">\n");
+            if !run.is_success() {
+                let tag = match run.result {
+                    TestResult::WrongOutput { .. } => "failure",
+                    _ => "error",
+                };
+                let kind = run.result.kind();
+                xml.push_str(&format!(
+                    "      <{tag} type=\"{}\" message=\"{}\"/>\n",
+                    kind,
+                    kind.replace('_', " ")
+                ));
+            }
+            if let Some(stdout) = run.result.stdout() {
+                xml.push_str(&format!("      <system-out>{}</system-out>\n", escape_xml(stdout)));
+            }
+            if let Some(stderr) = run.result.stderr() {
+                xml.push_str(&format!("      <system-err>{}</system-err>\n", escape_xml(stderr)));
+            }
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+
+    /// Renders the report as TAP version 13. Details about every testcase
+    /// that was run are included as YAML blocks.
+    pub fn to_tap(&self) -> String {
+        let mut tap = format!("TAP version 13\n1..{}\n", self.testcases.len());
+
+        for (num, test) in (1..).zip(&self.testcases) {
+            // '#' starts a directive in TAP so it has to be escaped
+            let description = test.title.replace('\\', "\\\\").replace('#', "\\#");
+            let Some(run) = &test.run else {
+                tap.push_str(&format!("ok {num} - {description} # SKIP not run\n"));
+                continue
+            };
+            let status = if run.is_success() { "ok" } else { "not ok" };
+            tap.push_str(&format!("{status} {num} - {description}\n"));
+            tap.push_str("  ---\n");
+            tap.push_str(&format!("  index: {}\n", test.index));
+            tap.push_str(&format!("  exit: {}\n", run.result.kind()));
+            tap.push_str(&format!("  duration: {:.3}\n", run.duration.as_secs_f64()));
+            if let Some(stdout) = run.result.stdout() {
+                tap.push_str(&yaml_block("stdout", stdout));
+            }
+            if let Some(stderr) = run.result.stderr() {
+                tap.push_str(&yaml_block("stderr", stderr));
+            }
+            tap.push_str("  ...\n");
+        }
+
+        tap
+    }
+}
+
+/// Formats `text` as a YAML literal block scalar nested in a TAP YAML block.
+fn yaml_block(key: &str, text: &str) -> String {
+    if text.is_empty() {
+        return format!("  {key}: ''\n")
+    }
+    // Explicit indentation indicator in case the first line starts with spaces
+    let mut block = format!("  {key}: |2\n");
+    for line in text.lines() {
+        block.push_str(&format!("    {line}\n"));
+    }
+    block
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Most control characters are not allowed in XML documents
+            ch if ch.is_ascii_control() && !matches!(ch, '\n' | '\r' | '\t') => {}
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn sample_report() -> SuiteReport {
+        let clash = crate::test_helper::sample_puzzle("stub_and_solution_tester").unwrap();
+        let testcases = &clash.testcases()[..3];
+        let mut report = SuiteReport::new("sample", testcases);
+        report.record(
+            &testcases[0],
+            TestRun {
+                result: TestResult::Success {
+                    stdout: String::from("fine"),
+                    stderr: String::from("debug"),
+                },
+                duration: Duration::from_millis(5),
+                peak_memory: None,
+            },
+        );
+        report.record(
+            &testcases[1],
+            TestRun {
+                result: TestResult::WrongOutput {
+                    stdout: String::from("<a & b>"),
+                    stderr: String::new(),
+                },
+                duration: Duration::from_millis(7),
+                peak_memory: Some(1024),
+            },
+        );
+        report
+    }
+
+    #[test]
+    fn report_counts_tests_and_validators_separately() {
+        let report = sample_report();
+        assert_eq!(report.tests_passed(), (1, 2));
+        assert_eq!(report.validators_passed(), (0, 1));
+        assert!(!report.all_passed());
+    }
+
+    #[test]
+    fn json_report_flattens_test_runs() {
+        let json: serde_json::Value = serde_json::from_str(&sample_report().to_json()).unwrap();
+        let testcases = json["testcases"].as_array().unwrap();
+        assert_eq!(testcases[0]["exit"], "success");
+        assert_eq!(testcases[0]["duration"], 0.005);
+        assert_eq!(testcases[0]["stdout"], "fine");
+        assert_eq!(testcases[0]["stderr"], "debug");
+        assert_eq!(testcases[1]["exit"], "wrong_output");
+        assert_eq!(testcases[1]["stdout"], "<a & b>");
+        assert!(testcases[2].get("exit").is_none());
+    }
+
+    #[test]
+    fn junit_report_escapes_output() {
+        let xml = sample_report().to_junit();
+        assert!(xml.contains(r#"tests="3" failures="1" errors="0" skipped="1""#));
+        assert!(xml.contains("<system-out>&lt;a &amp; b&gt;</system-out>"));
+        assert!(xml.contains("<system-out>fine</system-out>\n      <system-err>debug</system-err>"));
+        assert!(xml.contains("<skipped/>"));
+    }
+
+    #[test]
+    fn tap_report_has_plan_and_directives() {
+        let tap = sample_report().to_tap();
+        let lines: Vec<&str> = tap.lines().collect();
+        assert_eq!(lines[0], "TAP version 13");
+        assert_eq!(lines[1], "1..3");
+        assert_eq!(lines[2], "ok 1 - Test 1");
+        assert!(lines.contains(&"  stdout: |2"));
+        assert!(lines.contains(&"    fine"));
+        assert!(lines.contains(&"not ok 2 - Validator 1"));
+        assert_eq!(lines.last(), Some(&"ok 3 - Test 2 # SKIP not run"));
+    }
+}
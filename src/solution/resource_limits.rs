@@ -0,0 +1,169 @@
+use std::process::Command;
+use std::time::Duration;
+
+/// Limits that are enforced on a solution command in addition to the
+/// wall-clock timeout. All limits are disabled by default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceLimits {
+    /// Maximum memory of the solution in bytes, enforced with `RLIMIT_DATA`
+    /// on Unix-like platforms, so it also applies to the processes started by
+    /// the solution. Allocations beyond the limit fail, which usually makes
+    /// the solution crash. The crash is reported as
+    /// [MemoryLimitExceeded](super::TestResult::MemoryLimitExceeded) if the
+    /// data segment of the solution was measured to grow past half the limit,
+    /// as a runtime error otherwise.
+    pub memory: Option<u64>,
+    /// Maximum CPU time in seconds, enforced with `RLIMIT_CPU` on Unix-like
+    /// platforms.
+    pub cpu_time: Option<u64>,
+    /// Maximum number of bytes the solution may print to STDOUT. The same
+    /// limit is used to truncate STDERR, but exceeding it on STDERR is not an
+    /// error.
+    pub output: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Returns true if limits need to be applied to the command itself
+    /// (instead of being checked while it runs).
+    pub(super) fn has_rlimits(&self) -> bool {
+        cfg!(unix) && (self.cpu_time.is_some() || self.memory.is_some())
+    }
+
+    #[cfg(unix)]
+    pub(super) fn apply_rlimits(&self, command: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        let cpu_time = self.cpu_time;
+        let memory = self.memory;
+
+        // SAFETY: the closure runs in the forked child before exec, so it must
+        // be async-signal-safe. It only calls setrlimit and does not allocate.
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(cpu_time) = cpu_time {
+                    // The soft limit sends SIGXCPU, the hard limit a second
+                    // later SIGKILLs solutions that ignore it.
+                    let limit = libc::rlimit {
+                        rlim_cur: cpu_time as libc::rlim_t,
+                        rlim_max: cpu_time.saturating_add(1) as libc::rlim_t,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_CPU, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error())
+                    }
+                }
+                if let Some(memory) = memory {
+                    let limit = libc::rlimit {
+                        rlim_cur: memory as libc::rlim_t,
+                        rlim_max: memory as libc::rlim_t,
+                    };
+                    if libc::setrlimit(libc::RLIMIT_DATA, &limit) != 0 {
+                        return Err(std::io::Error::last_os_error())
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(super) fn apply_rlimits(&self, _command: &mut Command) {}
+
+    /// Returns true if a solution that failed was most likely stopped by the
+    /// memory limit. Allocations that fail are not accounted anywhere, so
+    /// this checks whether the data segment (what `RLIMIT_DATA` limits) grew
+    /// past half the limit: a buffer that can not double in size is the
+    /// usual way to run out of memory.
+    pub(super) fn exceeded_memory(&self, usage: &Usage) -> bool {
+        match (self.memory, usage.peak_data) {
+            (Some(max_memory), Some(peak_data)) => peak_data >= max_memory / 2,
+            _ => false,
+        }
+    }
+
+    /// Returns true if the process was stopped for exceeding its `RLIMIT_CPU`:
+    /// by SIGXCPU at the soft limit, or by SIGKILL at the hard limit if it
+    /// ignored SIGXCPU. A SIGKILL only counts if the process used up its CPU
+    /// time, since it may also come from elsewhere (e.g. the OOM killer).
+    #[cfg(unix)]
+    pub(super) fn exceeded_cpu_time(&self, status: &std::process::ExitStatus, usage: &Usage) -> bool {
+        use std::os::unix::process::ExitStatusExt;
+
+        let Some(max_cpu_time) = self.cpu_time else {
+            return false
+        };
+        match status.signal() {
+            Some(libc::SIGXCPU) => true,
+            Some(libc::SIGKILL) => match usage.cpu_time {
+                Some(cpu_time) => cpu_time.as_secs() >= max_cpu_time,
+                // Without measurements the hard limit is the likely culprit
+                None => !CAN_MEASURE_USAGE,
+            },
+            _ => false,
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(super) fn exceeded_cpu_time(&self, _status: &std::process::ExitStatus, _usage: &Usage) -> bool {
+        false
+    }
+}
+
+/// True if [Usage::measure] is supported on this platform.
+pub(super) const CAN_MEASURE_USAGE: bool = cfg!(target_os = "linux");
+
+/// Resource usage of a process, sampled while it runs. Every field is the
+/// highest value seen so far.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Usage {
+    /// Peak resident memory in bytes, which is what gets reported.
+    pub peak_memory: Option<u64>,
+    /// Peak size of the data segment in bytes.
+    pub peak_data: Option<u64>,
+    /// CPU time used by the process.
+    pub cpu_time: Option<Duration>,
+}
+
+impl Usage {
+    /// Records a new sample of the usage of the process `pid`.
+    pub fn measure(&mut self, pid: u32) {
+        let sample = Usage::sample(pid);
+        self.peak_memory = self.peak_memory.max(sample.peak_memory);
+        self.peak_data = self.peak_data.max(sample.peak_data);
+        self.cpu_time = self.cpu_time.max(sample.cpu_time);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn sample(pid: u32) -> Usage {
+        let status = std::fs::read_to_string(format!("/proc/{pid}/status")).unwrap_or_default();
+        let kilobytes = |field: &str| -> Option<u64> {
+            let line = status.lines().find_map(|line| line.strip_prefix(field))?;
+            let kilobytes: u64 = line.trim().trim_end_matches("kB").trim().parse().ok()?;
+            Some(kilobytes.saturating_mul(1024))
+        };
+
+        // utime and stime are the 14th and 15th fields, counted after the
+        // command name since it may contain spaces
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).unwrap_or_default();
+        let fields: Vec<&str> = stat.rsplit(')').next().unwrap_or_default().split_whitespace().collect();
+        let ticks = |index: usize| fields.get(index).and_then(|field| field.parse::<u64>().ok());
+        // SAFETY: sysconf has no preconditions
+        let ticks_per_sec = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        let cpu_time = match (ticks(11), ticks(12)) {
+            (Some(utime), Some(stime)) if ticks_per_sec > 0 => {
+                Some(Duration::from_secs_f64((utime + stime) as f64 / ticks_per_sec as f64))
+            }
+            _ => None,
+        };
+
+        Usage {
+            peak_memory: kilobytes("VmHWM:"),
+            peak_data: kilobytes("VmData:"),
+            cpu_time,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn sample(_pid: u32) -> Usage {
+        Usage::default()
+    }
+}
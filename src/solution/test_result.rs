@@ -1,18 +1,31 @@
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use super::Checker;
+use crate::clash::Testcase;
+
 pub enum CommandExit {
     Ok,
     Error,
     Timeout,
+    MemoryLimitExceeded,
+    OutputLimitExceeded,
 }
 
 /// Represents the outcome of running a testcase. [TestResult::Success] means
-/// the output of a solution command matched the `test_out` field of the
-/// [Testcase](crate::clash::Testcase).
-#[derive(Debug, Clone)]
+/// the output of a solution command was accepted by a [Checker] (by default it
+/// has to match the `test_out` field of the [Testcase]).
+///
+/// When serialized, the variant name is stored in the `exit` field as
+/// `snake_case` (see [TestResult::kind]).
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "exit", rename_all = "snake_case")]
 pub enum TestResult {
     /// Solution command produced the expected output. A test run is considered
     /// a success even if it runs into a runtime error or times out if its
     /// output was correct (just like it works on CodinGame).
-    Success,
+    Success { stdout: String, stderr: String },
     /// Solution command failed to run. This may happen for example if the
     /// executable does not exist or if the current user does not have
     /// permission to execute it.
@@ -22,16 +35,38 @@ pub enum TestResult {
     WrongOutput { stdout: String, stderr: String },
     /// Solution command encountered a runtime error (exited non-zero).
     RuntimeError { stdout: String, stderr: String },
-    /// Solution command timed out.
+    /// Solution command timed out (or ran out of CPU time).
     Timeout { stdout: String, stderr: String },
+    /// Solution command was killed for using more memory than allowed.
+    MemoryLimitExceeded { stdout: String, stderr: String },
+    /// Solution command printed more than allowed. Unlike with other failures
+    /// this is never considered a success, since `stdout` is truncated.
+    OutputLimitExceeded { stdout: String, stderr: String },
 }
 
 impl TestResult {
     pub(crate) fn from_output(
-        expected: &str,
+        testcase: &Testcase,
+        checker: &dyn Checker,
         stdout: Vec<u8>,
         stderr: Vec<u8>,
         exit_status: CommandExit,
+    ) -> Self {
+        Self::classify(stdout, stderr, exit_status, |stdout| checker.check(testcase, stdout))
+    }
+
+    /// Result of a command whose output is not checked: it is a success if
+    /// the command exits normally.
+    pub(crate) fn from_exit(stdout: Vec<u8>, stderr: Vec<u8>, exit_status: CommandExit) -> Self {
+        let exited_normally = matches!(exit_status, CommandExit::Ok);
+        Self::classify(stdout, stderr, exit_status, |_| exited_normally)
+    }
+
+    fn classify(
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+        exit_status: CommandExit,
+        accepted: impl FnOnce(&str) -> bool,
     ) -> Self {
         let stdout = String::from_utf8(stdout)
             .unwrap_or_default()
@@ -41,8 +76,10 @@ impl TestResult {
         let stderr = String::from_utf8(stderr).unwrap_or_default();
 
         match exit_status {
-            _ if stdout == expected.trim_end() => TestResult::Success,
+            CommandExit::OutputLimitExceeded => TestResult::OutputLimitExceeded { stdout, stderr },
+            _ if accepted(&stdout) => TestResult::Success { stdout, stderr },
             CommandExit::Timeout => TestResult::Timeout { stdout, stderr },
+            CommandExit::MemoryLimitExceeded => TestResult::MemoryLimitExceeded { stdout, stderr },
             CommandExit::Ok => TestResult::WrongOutput { stdout, stderr },
             CommandExit::Error => TestResult::RuntimeError { stdout, stderr },
         }
@@ -51,55 +88,132 @@ impl TestResult {
     /// Returns true if the testcase passed. A testcase passes if the output
     /// of the solution command matches the expected output.
     pub fn is_success(&self) -> bool {
-        matches!(self, TestResult::Success)
+        matches!(self, TestResult::Success { .. })
+    }
+
+    /// Short `snake_case` name of the variant, e.g. `"wrong_output"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TestResult::Success { .. } => "success",
+            TestResult::UnableToRun { .. } => "unable_to_run",
+            TestResult::WrongOutput { .. } => "wrong_output",
+            TestResult::RuntimeError { .. } => "runtime_error",
+            TestResult::Timeout { .. } => "timeout",
+            TestResult::MemoryLimitExceeded { .. } => "memory_limit_exceeded",
+            TestResult::OutputLimitExceeded { .. } => "output_limit_exceeded",
+        }
+    }
+
+    /// What the solution command printed to STDOUT, if the result has it.
+    pub fn stdout(&self) -> Option<&str> {
+        match self {
+            TestResult::UnableToRun { .. } => None,
+            TestResult::Success { stdout, .. }
+            | TestResult::WrongOutput { stdout, .. }
+            | TestResult::RuntimeError { stdout, .. }
+            | TestResult::Timeout { stdout, .. }
+            | TestResult::MemoryLimitExceeded { stdout, .. }
+            | TestResult::OutputLimitExceeded { stdout, .. } => Some(stdout),
+        }
+    }
+
+    /// What the solution command printed to STDERR (or the reason it could
+    /// not be run), if the result has it.
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            TestResult::UnableToRun { error_msg } => Some(error_msg),
+            TestResult::Success { stderr, .. }
+            | TestResult::WrongOutput { stderr, .. }
+            | TestResult::RuntimeError { stderr, .. }
+            | TestResult::Timeout { stderr, .. }
+            | TestResult::MemoryLimitExceeded { stderr, .. }
+            | TestResult::OutputLimitExceeded { stderr, .. } => Some(stderr),
+        }
+    }
+}
+
+/// A [TestResult] together with measurements from the run that produced it.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestRun {
+    #[serde(flatten)]
+    pub result: TestResult,
+    /// Wall-clock time the solution command ran for. Serialized as seconds.
+    #[serde(serialize_with = "serialize_secs")]
+    pub duration: Duration,
+    /// Highest resident memory usage of the solution process in bytes. It is
+    /// sampled while the solution runs, so it is `None` on platforms where
+    /// that is not supported and for solutions that finish very quickly.
+    pub peak_memory: Option<u64>,
+}
+
+impl TestRun {
+    /// Shorthand for `self.result.is_success()`.
+    pub fn is_success(&self) -> bool {
+        self.result.is_success()
     }
 }
 
+fn serialize_secs<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::solution::ExactChecker;
+
+    fn result_of(expected: &str, stdout: Vec<u8>, stderr: Vec<u8>, exit_status: CommandExit) -> TestResult {
+        let testcase = Testcase {
+            index: 1,
+            title: String::from("Test #1"),
+            test_in: String::new(),
+            test_out: expected.to_string(),
+            is_validator: false,
+        };
+        TestResult::from_output(&testcase, &ExactChecker, stdout, stderr, exit_status)
+    }
 
     #[test]
     fn test_testresult_success() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
+        let result = result_of("123", "123".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
     }
 
     #[test]
     fn test_testresult_success_with_trailing_whitespace() {
-        let result = TestResult::from_output("abc\n", "abc".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
-        let result = TestResult::from_output("abc", "abc\r\n".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
+        let result = result_of("abc\n", "abc".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
+        let result = result_of("abc", "abc\r\n".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
     }
 
     #[test]
     fn test_testresult_success_normalized_line_endings() {
-        let result = TestResult::from_output("a\nb\nc", "a\r\nb\r\nc".into(), vec![], CommandExit::Ok);
-        assert!(matches!(result, TestResult::Success));
+        let result = result_of("a\nb\nc", "a\r\nb\r\nc".into(), vec![], CommandExit::Ok);
+        assert!(matches!(result, TestResult::Success { .. }));
     }
 
     #[test]
     fn test_testresult_success_on_timeout() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Timeout);
+        let result = result_of("123", "123".into(), vec![], CommandExit::Timeout);
         assert!(
-            matches!(result, TestResult::Success),
+            matches!(result, TestResult::Success { .. }),
             "TestResult should be `Success` when stdout is correct even if execution timed out"
         )
     }
 
     #[test]
     fn test_testresult_success_on_runtime_error() {
-        let result = TestResult::from_output("123", "123".into(), vec![], CommandExit::Error);
+        let result = result_of("123", "123".into(), vec![], CommandExit::Error);
         assert!(
-            matches!(result, TestResult::Success),
+            matches!(result, TestResult::Success { .. }),
             "TestResult should be `Success` when stdout is correct even if a runtime error occurred"
         )
     }
 
     #[test]
     fn test_testresult_wrong_output() {
-        let result = TestResult::from_output("x\ny\nz", "yyy".into(), "zzz".into(), CommandExit::Ok);
+        let result = result_of("x\ny\nz", "yyy".into(), "zzz".into(), CommandExit::Ok);
         match result {
             TestResult::WrongOutput { stdout, stderr } => {
                 assert_eq!(stdout, "yyy");
@@ -111,7 +225,7 @@ mod tests {
 
     #[test]
     fn test_testresult_timed_out() {
-        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Timeout);
+        let result = result_of("xxx", "yyy".into(), "zzz".into(), CommandExit::Timeout);
         match result {
             TestResult::Timeout { stdout, stderr } => {
                 assert_eq!(stdout, "yyy");
@@ -123,7 +237,7 @@ mod tests {
 
     #[test]
     fn test_testresult_runtime_error() {
-        let result = TestResult::from_output("xxx", "yyy".into(), "zzz".into(), CommandExit::Error);
+        let result = result_of("xxx", "yyy".into(), "zzz".into(), CommandExit::Error);
         match result {
             TestResult::RuntimeError { stdout, stderr } => {
                 assert_eq!(stdout, "yyy");
@@ -132,4 +246,19 @@ mod tests {
             other => panic!("expected TestResult::RuntimeError but found {:?}", other),
         }
     }
+
+    #[test]
+    fn test_testresult_memory_limit_exceeded() {
+        let result = result_of("xxx", "yyy".into(), "zzz".into(), CommandExit::MemoryLimitExceeded);
+        assert!(matches!(result, TestResult::MemoryLimitExceeded { .. }));
+    }
+
+    #[test]
+    fn test_testresult_output_limit_exceeded_is_never_success() {
+        let result = result_of("123", "123".into(), vec![], CommandExit::OutputLimitExceeded);
+        assert!(
+            matches!(result, TestResult::OutputLimitExceeded { .. }),
+            "TestResult should not be `Success` when the output was truncated"
+        )
+    }
 }
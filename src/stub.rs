@@ -1,3 +1,5 @@
+mod input_check;
+mod input_gen;
 mod language;
 mod parser;
 mod preprocessor;
@@ -6,7 +8,10 @@ mod stub_config;
 
 use anyhow::Result;
 use indoc::indoc;
+pub use input_check::{check_input, InputMismatch};
+pub use input_gen::{generate_input, generate_shrinkable_input, shrink_input, GeneratedInput};
 use language::Language;
+pub use parser::StubParseError;
 use preprocessor::Renderable;
 use serde::Serialize;
 pub use stub_config::StubConfig;
@@ -67,22 +72,26 @@ enum VarType {
     String,
 }
 
-impl<'a> VarType {
-    fn new_unsized(value: &'a str) -> Self {
+impl VarType {
+    /// The type of `int`, `float`, `long` and `bool` variables, `None` for
+    /// other type names.
+    fn new_unsized(value: &str) -> Option<Self> {
         match value {
-            "int" => VarType::Int,
-            "float" => VarType::Float,
-            "long" => VarType::Long,
-            "bool" => VarType::Bool,
-            other => panic!("No unsized variable type: {other}"),
+            "int" => Some(VarType::Int),
+            "float" => Some(VarType::Float),
+            "long" => Some(VarType::Long),
+            "bool" => Some(VarType::Bool),
+            _ => None,
         }
     }
 
-    fn new_sized(value: &'a str) -> Self {
+    /// The type of `word(N)` and `string(N)` variables, `None` for other type
+    /// names.
+    fn new_sized(value: &str) -> Option<Self> {
         match value {
-            "word" => VarType::Word,
-            "string" => VarType::String,
-            other => panic!("No sized variable type: {other}"),
+            "word" => Some(VarType::Word),
+            "string" => Some(VarType::String),
+            _ => None,
         }
     }
 }
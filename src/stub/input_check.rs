@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::Result;
+
+use super::parser::parse_generator_stub;
+use super::{Cmd, VarType, VariableCommand};
+
+/// The first place where a testcase input does not follow the input format
+/// described by a stub generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMismatch {
+    /// Line of the input, starting from 1.
+    pub line: usize,
+    /// Space separated token of the line, starting from 1. `None` when the
+    /// whole line is wrong, e.g. when it is missing.
+    pub token: Option<usize>,
+    pub message: String,
+}
+
+impl InputMismatch {
+    fn new(line: usize, token: Option<usize>, message: &str) -> Self {
+        InputMismatch {
+            line: line.max(1),
+            token,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for InputMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.token {
+            Some(token) => write!(f, "line {}, token {}: {}", self.line, token, self.message),
+            None => write!(f, "line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+/// Checks that `input` can be read by the stub of `generator`: every line
+/// that the stub reads is present with the right number of values, the
+/// values have the right types and words and strings fit in their maximum
+/// length. Returns `Ok(None)` if the input is valid, or an error if the stub
+/// generator itself can not be parsed.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::stub::check_input;
+///
+/// let generator = "read n:int\nloop n read name:word(5)\nwrite answer";
+/// assert_eq!(check_input(generator, "2\nalice\nbob").unwrap(), None);
+///
+/// let mismatch = check_input(generator, "2\nalice\nbobby!").unwrap().unwrap();
+/// assert_eq!(mismatch.to_string(), "line 3, token 1: name is longer than 5 characters: \"bobby!\"");
+/// ```
+pub fn check_input(generator: &str, input: &str) -> Result<Option<InputMismatch>> {
+    let stub = parse_generator_stub(generator)?;
+    let input = input.strip_suffix('\n').unwrap_or(input);
+    let mut checker = InputChecker {
+        lines: input.split('\n').collect(),
+        next_line: 0,
+        values: HashMap::new(),
+    };
+    Ok(checker.check(&stub.commands).err())
+}
+
+struct InputChecker<'a> {
+    lines: Vec<&'a str>,
+    /// Index of the next line to read.
+    next_line: usize,
+    /// Values of the integer variables read so far, used as loop counts and
+    /// lengths.
+    values: HashMap<String, i64>,
+}
+
+impl<'a> InputChecker<'a> {
+    fn check(&mut self, commands: &[Cmd]) -> Result<(), InputMismatch> {
+        for cmd in commands {
+            self.check_cmd(cmd)?;
+        }
+        if let Some(offset) = self.lines[self.next_line.min(self.lines.len())..]
+            .iter()
+            .position(|line| !line.is_empty())
+        {
+            return Err(InputMismatch::new(
+                self.next_line + offset + 1,
+                None,
+                "the stub does not read this line",
+            ))
+        }
+        Ok(())
+    }
+
+    fn check_cmd(&mut self, cmd: &Cmd) -> Result<(), InputMismatch> {
+        match cmd {
+            Cmd::Read(variables) => {
+                let (line_number, line) = self.read_line(variables)?;
+                // A string at the end of the line takes the rest of the line
+                let tokens: Vec<&str> = match variables.last() {
+                    Some(var) if var.var_type == VarType::String => {
+                        line.splitn(variables.len(), ' ').collect()
+                    }
+                    _ => line.split(' ').collect(),
+                };
+                if tokens.len() != variables.len() {
+                    let message = format!("expected {} values, got {}", variables.len(), tokens.len());
+                    return Err(InputMismatch::new(line_number, None, &message))
+                }
+                for (index, (var, token)) in variables.iter().zip(tokens).enumerate() {
+                    self.check_value(var, token, line_number, index + 1)?;
+                }
+            }
+            Cmd::Loop { count_var, command } => {
+                let count = self.resolve(count_var, "loop count")?;
+                for _ in 0..count {
+                    self.check_cmd(command)?;
+                }
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                let count = self.resolve(count_var, "loopline count")?;
+                let (line_number, line) = self.read_line(variables)?;
+                let tokens: Vec<&str> = if line.is_empty() {
+                    Vec::new()
+                } else {
+                    line.split(' ').collect()
+                };
+                let Some(expected) =
+                    usize::try_from(count).ok().and_then(|count| count.checked_mul(variables.len()))
+                else {
+                    let message = format!("too many values ({} = {})", count_var, count);
+                    return Err(InputMismatch::new(line_number, None, &message))
+                };
+                if tokens.len() != expected {
+                    let message = format!(
+                        "expected {} values ({} = {}), got {}",
+                        expected,
+                        count_var,
+                        count,
+                        tokens.len()
+                    );
+                    return Err(InputMismatch::new(line_number, None, &message))
+                }
+                for (index, (var, token)) in variables.iter().cycle().zip(tokens).enumerate() {
+                    self.check_value(var, token, line_number, index + 1)?;
+                }
+            }
+            Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+        }
+        Ok(())
+    }
+
+    fn read_line(&mut self, variables: &[VariableCommand]) -> Result<(usize, &'a str), InputMismatch> {
+        let line_number = self.next_line + 1;
+        match self.lines.get(self.next_line) {
+            Some(line) => {
+                self.next_line += 1;
+                Ok((line_number, line))
+            }
+            None => {
+                let idents: Vec<&str> = variables.iter().map(|var| var.ident.as_str()).collect();
+                let message = format!("the input ended, expected a line with {}", idents.join(" "));
+                Err(InputMismatch::new(line_number, None, &message))
+            }
+        }
+    }
+
+    fn check_value(
+        &mut self,
+        var: &VariableCommand,
+        token: &str,
+        line: usize,
+        token_number: usize,
+    ) -> Result<(), InputMismatch> {
+        let ident = &var.ident;
+        let error = |message: String| Err(InputMismatch::new(line, Some(token_number), &message));
+        match var.var_type {
+            VarType::Int | VarType::Long => {
+                let value = match var.var_type {
+                    VarType::Int => token.parse::<i32>().map(i64::from),
+                    _ => token.parse::<i64>(),
+                };
+                let Ok(value) = value else {
+                    let kind = if var.var_type == VarType::Int {
+                        "an int"
+                    } else {
+                        "a long"
+                    };
+                    return error(format!("expected {} for {}, got {:?}", kind, ident, token))
+                };
+                self.values.insert(ident.to_string(), value);
+            }
+            VarType::Float if token.parse::<f64>().is_err() => {
+                return error(format!("expected a float for {}, got {:?}", ident, token))
+            }
+            VarType::Bool if token != "0" && token != "1" => {
+                return error(format!("expected a bool (0 or 1) for {}, got {:?}", ident, token))
+            }
+            VarType::Word if token.is_empty() => {
+                return error(format!("expected a word for {}, got nothing", ident))
+            }
+            VarType::Word | VarType::String => {
+                if let Some(max_length) = &var.max_length {
+                    let max_length = self.resolve(max_length, "length")?;
+                    if token.chars().count() as i64 > max_length {
+                        return error(format!(
+                            "{} is longer than {} characters: {:?}",
+                            ident, max_length, token
+                        ))
+                    }
+                }
+            }
+            VarType::Float | VarType::Bool => {}
+        }
+        Ok(())
+    }
+
+    /// Value of a loop count or a length, which is either a number or an
+    /// integer variable read earlier.
+    fn resolve(&self, count: &str, what: &str) -> Result<i64, InputMismatch> {
+        let value = count.parse::<i64>().ok().or_else(|| self.values.get(count).copied());
+        match value {
+            Some(value) if value >= 0 => Ok(value),
+            Some(value) => Err(InputMismatch::new(
+                self.next_line,
+                None,
+                &format!("negative {} {} = {}", what, count, value),
+            )),
+            None => Err(InputMismatch::new(
+                self.next_line,
+                None,
+                &format!("{} {} is not an integer read earlier", what, count),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    const GENERATOR: &str = indoc! {r##"
+        read n:int name:word(4)
+        loop n read x:int y:float
+        loopline n flag:bool
+        read line:string(10)
+        write answer
+    "##};
+
+    fn mismatch(input: &str) -> Option<String> {
+        check_input(GENERATOR, input).unwrap().map(|mismatch| mismatch.to_string())
+    }
+
+    #[test]
+    fn valid_input_has_no_mismatch() {
+        assert_eq!(mismatch("2 abcd\n1 0.5\n-3 2\n0 1\nhello you"), None);
+        assert_eq!(mismatch("0 a\n\n\n"), None);
+    }
+
+    #[test]
+    fn reports_where_the_input_diverges() {
+        let cases = [
+            (
+                "2 abcde\n1 0.5\n-3 2\n0 1\nhi",
+                "line 1, token 2: name is longer than 4 characters: \"abcde\"",
+            ),
+            ("x abc", "line 1, token 1: expected an int for n, got \"x\""),
+            ("2 abc\n1 0.5\n-3 two", "line 3, token 2: expected a float for y, got \"two\""),
+            ("2 abc\n1 0.5\n-3 2\n0 1 1\nhi", "line 4: expected 2 values (n = 2), got 3"),
+            (
+                "2 abc\n1 0.5\n-3 2\ntrue 1\nhi",
+                "line 4, token 1: expected a bool (0 or 1) for flag, got \"true\"",
+            ),
+            ("2 abc\n1 0.5\n-3 2\n0 1", "line 5: the input ended, expected a line with line"),
+            ("1 abc\n1 0.5 3", "line 2: expected 2 values, got 3"),
+            (
+                "0 a\n\nthis is too long",
+                "line 3, token 1: line is longer than 10 characters: \"this is too long\"",
+            ),
+            ("0 a\n\nfine\nextra", "line 4: the stub does not read this line"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(mismatch(input).as_deref(), Some(expected), "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn huge_loopline_count_is_a_mismatch() {
+        let generator = "loopline 9223372036854775807 a:int b:int c:int\nwrite answer";
+        let mismatch = check_input(generator, "1 2 3").unwrap().unwrap();
+        assert_eq!(
+            mismatch.to_string(),
+            "line 1: too many values (9223372036854775807 = 9223372036854775807)"
+        );
+    }
+}
@@ -0,0 +1,494 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::{Range, RangeInclusive};
+
+use anyhow::{anyhow, Result};
+use rand::Rng;
+
+use super::parser::parse_generator_stub;
+use super::{Cmd, VarType, VariableCommand};
+
+/// Range of the values, loop counts and lengths of variables that have no
+/// range of their own.
+const DEFAULT_RANGE: RangeInclusive<i64> = 1..=10;
+
+const LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// Generates a random input that the stub of `generator` can read.
+///
+/// `ranges` restricts the variables by name: the values of ints, longs and
+/// floats, and the lengths of words and strings. Variables without a range
+/// use `1..=10`. Since loop counts are variables too, their range controls
+/// the size of the input. Variables that are used as loop counts or lengths
+/// are never negative. Lengths never exceed the maximum length given in the
+/// stub generator, and words have at least one character.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use clashlib::stub::{check_input, generate_input};
+/// use rand::SeedableRng;
+///
+/// let generator = "read n:int\nloop n read name:word(5) age:int\nwrite answer";
+/// let ranges = HashMap::from([(String::from("n"), 3..=3)]);
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+/// let input = generate_input(generator, &ranges, &mut rng).unwrap();
+/// assert_eq!(input.lines().count(), 4);
+/// assert_eq!(check_input(generator, &input).unwrap(), None);
+/// ```
+pub fn generate_input<R: Rng + ?Sized>(
+    generator: &str,
+    ranges: &HashMap<String, RangeInclusive<i64>>,
+    rng: &mut R,
+) -> Result<String> {
+    let (input, _) = generate_input_with(generator, ranges, &mut |range| rng.gen_range(range))?;
+    Ok(input)
+}
+
+/// A generated input together with the random choices that produced it, so
+/// that [shrink_input] can look for a simpler input.
+#[derive(Debug, Clone)]
+pub struct GeneratedInput {
+    pub input: String,
+    choices: Vec<Choice>,
+    repetitions: Vec<Repetition>,
+}
+
+/// A number chosen while generating an input, with the number of its range
+/// that is closest to zero, which is what shrinking aims for.
+#[derive(Debug, Clone)]
+struct Choice {
+    range: RangeInclusive<i64>,
+    pivot: i64,
+    value: i64,
+}
+
+/// Choices made for one iteration of a loop, or for one character of a word
+/// or string, that go away if the choice `counter` is one less.
+#[derive(Debug, Clone)]
+struct Repetition {
+    counter: usize,
+    choices: Range<usize>,
+}
+
+impl GeneratedInput {
+    /// Choices sort in shortlex order: fewer choices are simpler, then
+    /// choices closer to their pivot.
+    fn complexity(&self) -> (usize, Vec<u64>) {
+        let distances = self.choices.iter().map(|choice| choice.value.abs_diff(choice.pivot)).collect();
+        (self.choices.len(), distances)
+    }
+}
+
+/// Like [generate_input], but keeps the choices made so that the input can be
+/// shrunk with [shrink_input].
+pub fn generate_shrinkable_input<R: Rng + ?Sized>(
+    generator: &str,
+    ranges: &HashMap<String, RangeInclusive<i64>>,
+    rng: &mut R,
+) -> Result<GeneratedInput> {
+    replay_choices(generator, ranges, |range| rng.gen_range(range))
+}
+
+/// Looks for a simpler input than `failing` for which `still_fails` returns
+/// true: with fewer loop iterations, shorter words and strings, and numbers
+/// closer to zero. Every input tried is generated from the stub generator, so
+/// it stays valid. At most `max_attempts` inputs are passed to `still_fails`.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use clashlib::stub::{generate_shrinkable_input, shrink_input};
+/// use rand::SeedableRng;
+///
+/// let generator = "read n:int\nloop n read x:int";
+/// let ranges = HashMap::from([(String::from("n"), 1..=5), (String::from("x"), 0..=1000)]);
+/// let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+/// let failing = generate_shrinkable_input(generator, &ranges, &mut rng).unwrap();
+/// // Pretend that solutions fail whenever some x is at least 500
+/// let fails = |input: &str| input.lines().skip(1).any(|x| x.parse::<i64>().unwrap() >= 500);
+/// assert!(fails(&failing.input));
+/// let shrunk = shrink_input(generator, &ranges, failing, fails, 1000).unwrap();
+/// assert_eq!(shrunk.input, "1\n500");
+/// ```
+pub fn shrink_input(
+    generator: &str,
+    ranges: &HashMap<String, RangeInclusive<i64>>,
+    failing: GeneratedInput,
+    mut still_fails: impl FnMut(&str) -> bool,
+    max_attempts: usize,
+) -> Result<GeneratedInput> {
+    let mut best = failing;
+    let mut attempts = 0;
+    'improve: loop {
+        for values in shrink_candidates(&best) {
+            let mut values = values.into_iter();
+            let candidate = replay_choices(generator, ranges, |range| {
+                values
+                    .next()
+                    .map_or(*range.start(), |value| value.clamp(*range.start(), *range.end()))
+            })?;
+            if candidate.complexity() >= best.complexity() {
+                continue
+            }
+            if attempts == max_attempts {
+                break 'improve
+            }
+            attempts += 1;
+            if still_fails(&candidate.input) {
+                best = candidate;
+                continue 'improve
+            }
+        }
+        break
+    }
+    Ok(best)
+}
+
+/// Values to replay instead of the choices of `input`: first without one of
+/// its repetitions, then with single choices moved closer to their pivot,
+/// from either side.
+fn shrink_candidates(input: &GeneratedInput) -> impl Iterator<Item = Vec<i64>> {
+    let values: Vec<i64> = input.choices.iter().map(|choice| choice.value).collect();
+    let removals = input.repetitions.clone().into_iter().rev().map({
+        let values = values.clone();
+        move |repetition| {
+            let mut candidate = values.clone();
+            candidate[repetition.counter] -= 1;
+            candidate.drain(repetition.choices);
+            candidate
+        }
+    });
+    let reductions = input.choices.clone().into_iter().enumerate().flat_map(move |(index, choice)| {
+        let distance = choice.value - choice.pivot;
+        // The pivot itself, then halfway there, a quarter of the way, ...
+        let steps = std::iter::once(distance).chain(
+            std::iter::successors(Some(distance / 2), |step| Some(step / 2)).take_while(|step| *step != 0),
+        );
+        let values = values.clone();
+        steps.flat_map(move |step| {
+            // The same distance on the other side of the pivot, if it is in
+            // the range
+            let mirrored = choice.pivot - (distance - step);
+            let mirrored =
+                Some(mirrored).filter(|value| *value != choice.value - step && choice.range.contains(value));
+            let values = values.clone();
+            std::iter::once(choice.value - step).chain(mirrored).map(move |value| {
+                let mut candidate = values.clone();
+                candidate[index] = value;
+                candidate
+            })
+        })
+    });
+    removals.chain(reductions)
+}
+
+/// Generates an input with `choose` and records its choices.
+fn replay_choices(
+    generator: &str,
+    ranges: &HashMap<String, RangeInclusive<i64>>,
+    mut choose: impl FnMut(RangeInclusive<i64>) -> i64,
+) -> Result<GeneratedInput> {
+    let mut choices = Vec::new();
+    let (input, repetitions) = generate_input_with(generator, ranges, &mut |range| {
+        let pivot = 0.clamp(*range.start(), *range.end());
+        let value = choose(range.clone());
+        choices.push(Choice { range, pivot, value });
+        value
+    })?;
+    Ok(GeneratedInput {
+        input,
+        choices,
+        repetitions,
+    })
+}
+
+/// Like [generate_input], with `choose` making every choice: it is called
+/// with a non-empty range and has to return a number in it. Also returns the
+/// repetitions of the input, in terms of the indices of the choices.
+fn generate_input_with(
+    generator: &str,
+    ranges: &HashMap<String, RangeInclusive<i64>>,
+    choose: &mut dyn FnMut(RangeInclusive<i64>) -> i64,
+) -> Result<(String, Vec<Repetition>)> {
+    let stub = parse_generator_stub(generator)?;
+    let mut counts = HashSet::new();
+    for cmd in &stub.commands {
+        collect_counts(cmd, &mut counts);
+    }
+    let mut input_gen = InputGen {
+        ranges,
+        counts,
+        choose,
+        num_choices: 0,
+        values: HashMap::new(),
+        value_choices: HashMap::new(),
+        repetitions: Vec::new(),
+        lines: Vec::new(),
+    };
+    for cmd in &stub.commands {
+        input_gen.generate_cmd(cmd)?;
+    }
+    Ok((input_gen.lines.join("\n"), input_gen.repetitions))
+}
+
+/// Adds the variables that `cmd` uses as loop counts or lengths to `counts`.
+fn collect_counts(cmd: &Cmd, counts: &mut HashSet<String>) {
+    let variables = match cmd {
+        Cmd::Read(variables) => variables,
+        Cmd::Loop { count_var, command } => {
+            counts.insert(count_var.clone());
+            return collect_counts(command, counts)
+        }
+        Cmd::LoopLine { count_var, variables } => {
+            counts.insert(count_var.clone());
+            variables
+        }
+        Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => return,
+    };
+    counts.extend(variables.iter().filter_map(|var| var.max_length.clone()));
+}
+
+struct InputGen<'a> {
+    ranges: &'a HashMap<String, RangeInclusive<i64>>,
+    /// Variables used as loop counts or lengths, which can not be negative.
+    counts: HashSet<String>,
+    choose: &'a mut dyn FnMut(RangeInclusive<i64>) -> i64,
+    num_choices: usize,
+    /// Values of the integer variables generated so far, used as loop counts
+    /// and lengths.
+    values: HashMap<String, i64>,
+    /// Index of the choice that gave each integer variable its value.
+    value_choices: HashMap<String, usize>,
+    repetitions: Vec<Repetition>,
+    lines: Vec<String>,
+}
+
+impl<'a> InputGen<'a> {
+    fn generate_cmd(&mut self, cmd: &Cmd) -> Result<()> {
+        match cmd {
+            Cmd::Read(variables) => {
+                let tokens = variables
+                    .iter()
+                    .enumerate()
+                    .map(|(index, var)| self.generate_value(var, index + 1 == variables.len()))
+                    .collect::<Result<Vec<_>>>()?;
+                self.lines.push(tokens.join(" "));
+            }
+            Cmd::Loop { count_var, command } => {
+                let counter = self.counter(count_var);
+                for _ in 0..self.resolve(count_var)? {
+                    let start = self.num_choices;
+                    self.generate_cmd(command)?;
+                    self.add_repetition(counter, start);
+                }
+            }
+            Cmd::LoopLine { count_var, variables } => {
+                let counter = self.counter(count_var);
+                let mut tokens = Vec::new();
+                for _ in 0..self.resolve(count_var)? {
+                    let start = self.num_choices;
+                    for var in variables {
+                        tokens.push(self.generate_value(var, false)?);
+                    }
+                    self.add_repetition(counter, start);
+                }
+                self.lines.push(tokens.join(" "));
+            }
+            Cmd::Write { .. } | Cmd::WriteJoin { .. } | Cmd::External(_) => {}
+        }
+        Ok(())
+    }
+
+    /// A random value for `var`. Only a string that ends its line can contain
+    /// spaces, since the stub reads it as the rest of the line.
+    fn generate_value(&mut self, var: &VariableCommand, ends_line: bool) -> Result<String> {
+        let range = self.ranges.get(&var.ident).cloned().unwrap_or(DEFAULT_RANGE);
+        let value = match var.var_type {
+            VarType::Int | VarType::Long => {
+                let min = if self.counts.contains(&var.ident) {
+                    0
+                } else if var.var_type == VarType::Int {
+                    i64::from(i32::MIN)
+                } else {
+                    i64::MIN
+                };
+                let max = match var.var_type {
+                    VarType::Int => i64::from(i32::MAX),
+                    _ => i64::MAX,
+                };
+                let range = clamp_range(range, min..=max);
+                self.value_choices.insert(var.ident.clone(), self.num_choices);
+                let value = self.choose(range);
+                self.values.insert(var.ident.clone(), value);
+                value.to_string()
+            }
+            // Floats get two decimals
+            VarType::Float => {
+                let hundredths =
+                    self.choose(range.start().saturating_mul(100)..=range.end().saturating_mul(100));
+                format!("{}", hundredths as f64 / 100.0)
+            }
+            VarType::Bool => self.choose(0..=1).to_string(),
+            VarType::Word | VarType::String => {
+                let max_length = match &var.max_length {
+                    Some(max_length) => self.resolve(max_length)?,
+                    None => i64::MAX,
+                };
+                let min_length = match var.var_type {
+                    VarType::Word => 1,
+                    _ => 1.min(max_length),
+                };
+                let counter = Some(self.num_choices);
+                let length =
+                    self.choose(clamp_range(range, min_length..=max_length.max(min_length))) as usize;
+                let spaces = var.var_type == VarType::String && ends_line;
+                (0..length)
+                    .map(|i| {
+                        let start = self.num_choices;
+                        let char = self.generate_char(spaces && i != 0 && i + 1 != length);
+                        self.add_repetition(counter, start);
+                        char
+                    })
+                    .collect()
+            }
+        };
+        Ok(value)
+    }
+
+    /// A random letter, or possibly a space if `allow_space`.
+    fn generate_char(&mut self, allow_space: bool) -> char {
+        let num_chars = if allow_space {
+            LETTERS.len() + 1
+        } else {
+            LETTERS.len()
+        };
+        let index = self.choose(0..=num_chars as i64 - 1) as usize;
+        LETTERS.get(index).map_or(' ', |&letter| letter as char)
+    }
+
+    fn choose(&mut self, range: RangeInclusive<i64>) -> i64 {
+        self.num_choices += 1;
+        (self.choose)(range)
+    }
+
+    /// Index of the choice that gave a loop count or length its value, `None`
+    /// if it is a number written in the stub generator.
+    fn counter(&self, count: &str) -> Option<usize> {
+        match count.parse::<i64>() {
+            Ok(_) => None,
+            Err(_) => self.value_choices.get(count).copied(),
+        }
+    }
+
+    /// Records the choices made since `start` as one repetition of `counter`.
+    fn add_repetition(&mut self, counter: Option<usize>, start: usize) {
+        if let Some(counter) = counter {
+            self.repetitions.push(Repetition {
+                counter,
+                choices: start..self.num_choices,
+            });
+        }
+    }
+
+    /// Value of a loop count or a length, which is either a number or an
+    /// integer variable generated earlier. Negative values count as zero.
+    fn resolve(&self, count: &str) -> Result<i64> {
+        let value = count.parse::<i64>().ok().or_else(|| self.values.get(count).copied());
+        let value =
+            value.ok_or_else(|| anyhow!("{} is not an integer that is read before it is used", count))?;
+        Ok(value.max(0))
+    }
+}
+
+/// The part of `range` inside `bounds`, or the nearest bound if they do not
+/// overlap.
+fn clamp_range(range: RangeInclusive<i64>, bounds: RangeInclusive<i64>) -> RangeInclusive<i64> {
+    let start = (*range.start()).clamp(*bounds.start(), *bounds.end());
+    let end = (*range.end()).clamp(start, *bounds.end());
+    start..=end
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::stub::check_input;
+
+    const GENERATOR: &str = indoc! {r##"
+        read n:int w:word(3) big:long
+        loop n read x:float s:string(20)
+        loopline n flag:bool id:int
+        read m:int
+        loop m loop 2 read name:word(m)
+        write answer
+    "##};
+
+    #[test]
+    fn generated_inputs_are_valid_and_reproducible() {
+        let ranges = HashMap::from([
+            (String::from("n"), 0..=5),
+            (String::from("big"), 10_000_000_000..=20_000_000_000),
+            (String::from("w"), 5..=10),
+        ]);
+        for seed in 0..50 {
+            let input = generate_input(GENERATOR, &ranges, &mut StdRng::seed_from_u64(seed)).unwrap();
+            let again = generate_input(GENERATOR, &ranges, &mut StdRng::seed_from_u64(seed)).unwrap();
+            assert_eq!(input, again);
+            assert_eq!(check_input(GENERATOR, &input).unwrap(), None, "input: {:?}", input);
+            // Lengths are capped by the stub generator
+            assert_eq!(input.split(' ').nth(1).unwrap().len(), 3);
+        }
+    }
+
+    #[test]
+    fn counts_are_not_negative_and_spaces_only_end_lines() {
+        let generator = "read n:int\nloop n read s:string(8) k:int t:string(8)\nread m:int\nread w:word(m)";
+        let ranges = HashMap::from([
+            (String::from("n"), -5..=3),
+            (String::from("s"), 3..=8),
+            (String::from("m"), -3..=0),
+        ]);
+        for seed in 0..50 {
+            let input = generate_input(generator, &ranges, &mut StdRng::seed_from_u64(seed)).unwrap();
+            let lines: Vec<&str> = input.lines().collect();
+            let n: usize = lines[0].parse().unwrap();
+            for line in &lines[1..=n] {
+                let tokens: Vec<&str> = line.splitn(3, ' ').collect();
+                assert!(tokens[0].len() >= 3 && tokens[1].parse::<i32>().is_ok(), "line: {:?}", line);
+            }
+            assert_eq!(lines[n + 1], "0");
+            assert_eq!(lines[n + 2].len(), 1);
+        }
+    }
+
+    #[test]
+    fn shrinking_removes_loop_iterations_and_lowers_numbers() {
+        let generator = "read n:int\nloop n read x:int w:word(10)";
+        let ranges = HashMap::from([(String::from("n"), 0..=20), (String::from("x"), -1000..=1000)]);
+        let fails =
+            |input: &str| input.lines().skip(1).any(|line| line.split(' ').next().unwrap().len() >= 3);
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let failing = generate_shrinkable_input(generator, &ranges, &mut rng).unwrap();
+            if !fails(&failing.input) {
+                continue
+            }
+            let shrunk = shrink_input(generator, &ranges, failing, fails, 10_000).unwrap();
+            assert_eq!(shrunk.input, "1\n-10 a");
+        }
+    }
+
+    #[test]
+    fn choices_within_the_ranges() {
+        let ranges = HashMap::from([(String::from("n"), 2..=2), (String::from("m"), 1..=1)]);
+        let (input, _) = generate_input_with(GENERATOR, &ranges, &mut |range| *range.start()).unwrap();
+        assert_eq!(input, "2 a 1\n1 a\n1 a\n0 1 0 1\n1\na\na");
+    }
+}
@@ -1,19 +1,103 @@
-use std::iter;
-
-use anyhow::{anyhow, Result};
+use std::{fmt, iter};
 
 use super::{Cmd, JoinTerm, Stub, VarType, VariableCommand};
 
+type Result<T> = std::result::Result<T, StubParseError>;
+
 pub fn parse_generator_stub(generator: &str) -> Result<Stub> {
     Parser::new(generator).parse()
 }
 
+/// An error in a stub generator, with the position of the token that caused
+/// it.
+///
+/// # Examples
+///
+/// ```
+/// use clashlib::stub::{generate, StubParseError};
+///
+/// let generator = "read n:int\nread name:text";
+/// let error = generate("python", generator).unwrap_err();
+/// let error = error.downcast_ref::<StubParseError>().unwrap();
+/// assert_eq!((error.line, error.column, error.length), (2, 11, 4));
+/// assert_eq!(
+///     error.render(generator),
+///     "Unknown variable type `text`, expected int, float, long, bool, word(N) or string(N)\n \
+///       --> line 2, column 11\n  |\n2 | read name:text\n  |           ^^^^"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StubParseError {
+    /// Line of the stub generator, starting from 1.
+    pub line: usize,
+    /// Column of the first character of the offending token, starting from 1.
+    pub column: usize,
+    /// Length of the offending token in characters, at least 1.
+    pub length: usize,
+    pub message: String,
+}
+
+impl StubParseError {
+    /// The error followed by the line of `generator` where it happened, with
+    /// the offending token underlined, like rustc does.
+    pub fn render(&self, generator: &str) -> String {
+        let source_line = generator.lines().nth(self.line - 1).unwrap_or_default();
+        let gutter = " ".repeat(self.line.to_string().len());
+        format!(
+            "{message}\n{gutter}--> line {line}, column {column}\n{gutter} |\n{line} | {source_line}\n{gutter} | \
+             {padding}{carets}",
+            message = self.message,
+            line = self.line,
+            column = self.column,
+            padding = " ".repeat(self.column - 1),
+            carets = "^".repeat(self.length),
+        )
+    }
+}
+
+impl fmt::Display for StubParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (line {}, column {})", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for StubParseError {}
+
+/// A space separated token of the stub generator, or the `"\n"` that ends
+/// each line, with its position.
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Token<'a> {
+    fn error(&self, message: impl Into<String>) -> StubParseError {
+        self.error_in(0, self.text.chars().count(), message)
+    }
+
+    /// An error about the characters of the token from `start` on.
+    fn error_in(&self, start: usize, length: usize, message: impl Into<String>) -> StubParseError {
+        StubParseError {
+            line: self.line,
+            column: self.column + start,
+            length: length.max(1),
+            message: message.into(),
+        }
+    }
+}
+
 /// A wrapper around an iterator of tokens in the CG stub. Contains all of the
 /// stub parsing logic.
 ///
 /// Exists solely to be consumed with `.parse()`
 struct Parser<'a> {
-    token_stream: Box<dyn Iterator<Item = &'a str> + 'a>,
+    source: &'a str,
+    token_stream: Box<dyn Iterator<Item = Token<'a>> + 'a>,
+    /// The token returned by the last call to `next_token`, where errors
+    /// about a missing token are reported.
+    last_token: Token<'a>,
     read_pairings: std::collections::BTreeMap<String, VarType>,
 }
 
@@ -24,9 +108,32 @@ impl<'a> Parser<'a> {
         // Essentially this puts a "\n" at the end of each line so the parser can tell
         // where the lines end. Unfortunately I cannot concat &strs which would
         // have made this much simpler.
-        let token_stream = stub.lines().flat_map(|line| line.split(' ').chain(iter::once("\n")));
+        let token_stream = stub.lines().enumerate().flat_map(|(line_index, line)| {
+            let mut column = 1;
+            let tokens = line.split(' ').map(move |text| {
+                let token = Token {
+                    text,
+                    line: line_index + 1,
+                    column,
+                };
+                column += text.chars().count() + 1;
+                token
+            });
+            let newline = Token {
+                text: "\n",
+                line: line_index + 1,
+                column: line.chars().count() + 1,
+            };
+            tokens.chain(iter::once(newline))
+        });
         Self {
+            source: stub,
             token_stream: Box::new(token_stream),
+            last_token: Token {
+                text: "",
+                line: 1,
+                column: 1,
+            },
             read_pairings: std::collections::BTreeMap::new(),
         }
     }
@@ -37,47 +144,49 @@ impl<'a> Parser<'a> {
 
         while let Some(token) = self.next_token() {
             match token {
-                "read"      => stub.commands.push(self.parse_read()),
-                "write"     => stub.commands.push(self.parse_write()),
-                "loop"      => stub.commands.push(self.parse_loop()),
-                "loopline"  => stub.commands.push(self.parse_loopline()),
+                "read"      => stub.commands.push(self.parse_read()?),
+                "write"     => stub.commands.push(self.parse_write()?),
+                "loop"      => stub.commands.push(self.parse_loop()?),
+                "loopline"  => stub.commands.push(self.parse_loopline()?),
                 "OUTPUT"    => self.parse_output_comment(&mut stub.commands),
                 "INPUT"     => self.parse_input_comment(&mut stub.commands),
                 "STATEMENT" => stub.statement = self.parse_text_block(),
-                "gameloop"  => return Err(anyhow!("Stub generator does not currently support the 'gameloop' command")),
+                "gameloop"  => return Err(self.last_token.error("The 'gameloop' command is not supported yet")),
                 "\n" | ""   => continue,
-                thing => panic!("Unknown token stub generator: '{}'", thing),
+                thing => return Err(self.last_token.error(format!(
+                    "Unknown command `{}`, expected read, write, loop, loopline, INPUT, OUTPUT or STATEMENT", thing
+                ))),
             };
         }
 
         Ok(stub)
     }
 
-    fn parse_read(&mut self) -> Cmd {
-        Cmd::Read(self.parse_variables())
+    fn parse_read(&mut self) -> Result<Cmd> {
+        Ok(Cmd::Read(self.parse_variables()?))
     }
 
-    fn parse_write(&mut self) -> Cmd {
+    fn parse_write(&mut self) -> Result<Cmd> {
         let mut lines = Vec::new();
 
         while let Some(line) = self.rest_of_line() {
             // NOTE: A join could be present on the first line
             if lines.is_empty() {
-                if let Some(write) = self.check_for_write_join(&line) {
-                    return write
+                if let Some(write) = self.check_for_write_join(&line)? {
+                    return Ok(write)
                 }
             }
 
             lines.push(line)
         }
 
-        Cmd::Write {
+        Ok(Cmd::Write {
             lines,
             output_comment: Vec::new(),
-        }
+        })
     }
 
-    fn check_for_write_join(&self, line: &str) -> Option<Cmd> {
+    fn check_for_write_join(&self, line: &str) -> Result<Option<Cmd>> {
         // NOTE: write•join()•rest⏎, with NOTHING inside the parens,
         //       gets parsed as a write and not as a write_join
         match line.replace("join()", "").split_once("join(") {
@@ -87,101 +196,171 @@ impl<'a> Parser<'a> {
                 if terms_string.split(',').any(|t| t.trim().is_empty()) {
                     // write•join("hi",,,•"Jim")⏎ should be rendered as a Write Cmd
                     // (I guess the CG parser fails due to consecutive commas)
-                    Some(Cmd::Write {
+                    Ok(Some(Cmd::Write {
                         lines: vec![line.to_string()],
                         output_comment: Vec::new(),
-                    })
+                    }))
                 } else {
                     // NOTE: write•join("a")⏎ is a valid join
-                    Some(self.parse_write_join(terms_string))
+                    Ok(Some(self.parse_write_join(terms_string)?))
                 }
             }
             // NOTE: write•join(⏎ gets parsed as a raw string
             //       and write parsing resumes
-            _ => None,
+            _ => Ok(None),
         }
     }
 
-    fn parse_write_join(&self, terms_string: &str) -> Cmd {
+    fn parse_write_join(&self, terms_string: &str) -> Result<Cmd> {
         let join_terms = terms_string
             .split(',')
             .map(|term| {
                 if term.contains('"') {
                     let ident = term.trim_matches(|c| c != '"').trim_matches('"').to_string();
-                    JoinTerm::new(ident, None)
+                    Ok(JoinTerm::new(ident, None))
                 } else {
                     let ident = term.trim().to_string();
                     match self.read_pairings.get(&ident) {
-                        Some(var_type) => JoinTerm::new(ident, Some(*var_type)),
-                        None => panic!("The JoinTerm '{}' was not previously initialized.", &ident),
+                        Some(var_type) => Ok(JoinTerm::new(ident, Some(*var_type))),
+                        None => Err(self.join_term_error(&ident)),
                     }
                 }
             })
-            .collect();
+            .collect::<Result<_>>()?;
 
-        Cmd::WriteJoin {
+        Ok(Cmd::WriteJoin {
             join_terms,
             output_comment: Vec::new(),
-        }
+        })
     }
 
-    fn parse_loop(&mut self) -> Cmd {
+    /// Error about a join term that is not a variable read earlier, on the
+    /// line of the write that was just parsed.
+    fn join_term_error(&self, ident: &str) -> StubParseError {
+        let line = self.source.lines().nth(self.last_token.line - 1).unwrap_or_default();
+        // Look for the term after the join it belongs to
+        let join_start = line
+            .match_indices("join(")
+            .find(|(offset, _)| !line[*offset..].starts_with("join()"))
+            .map_or(0, |(offset, _)| offset);
+        let offset = line[join_start..].find(ident).map_or(join_start, |offset| join_start + offset);
+        let line_start = Token {
+            text: line,
+            line: self.last_token.line,
+            column: 1,
+        };
+        line_start.error_in(
+            line[..offset].chars().count(),
+            ident.chars().count(),
+            format!("`{}` is neither a \"string\" nor a variable that was read before", ident),
+        )
+    }
+
+    fn parse_loop(&mut self) -> Result<Cmd> {
         match self.first_non_whitespace_token() {
-            None => panic!("Unexpected end of input: Loop stub not provided with loop count"),
-            Some(other) => Cmd::Loop {
+            None => Err(self.last_token.error("Unexpected end of input, expected the count of the loop")),
+            Some(other) => Ok(Cmd::Loop {
                 count_var: String::from(other),
-                command: Box::new(self.parse_loopable()),
-            },
+                command: Box::new(self.parse_loopable()?),
+            }),
         }
     }
 
-    fn parse_loopable(&mut self) -> Cmd {
+    fn parse_loopable(&mut self) -> Result<Cmd> {
         match self.first_non_whitespace_token() {
             Some("read") => self.parse_read(),
             Some("write") => self.parse_write(),
             Some("loopline") => self.parse_loopline(),
             Some("loop") => self.parse_loop(),
-            Some(thing) => panic!("Error parsing loop command in stub generator, got: {}", thing),
-            None => panic!("Unexpected end of input, expecting command to loop through"),
+            Some(thing) => Err(self.last_token.error(format!(
+                "Unknown command `{}` in loop, expected read, write, loop or loopline",
+                thing
+            ))),
+            None => Err(self.last_token.error("Unexpected end of input, expected the command to loop")),
         }
     }
 
-    fn parse_loopline(&mut self) -> Cmd {
+    fn parse_loopline(&mut self) -> Result<Cmd> {
         match self.first_non_whitespace_token() {
-            None => panic!("Unexpected end of input: Loopline stub not provided with count identifier"),
-            Some(other) => Cmd::LoopLine {
+            None => Err(self.last_token.error("Unexpected end of input, expected the count of the loopline")),
+            Some(other) => Ok(Cmd::LoopLine {
                 count_var: other.to_string(),
-                variables: self.parse_variables(),
-            },
+                variables: self.parse_variables()?,
+            }),
         }
     }
 
-    fn parse_variables(&mut self) -> Vec<VariableCommand> {
+    fn parse_variables(&mut self) -> Result<Vec<VariableCommand>> {
         let Some(tokens) = self.tokens_upto_newline() else {
-            panic!("Empty line after read keyword")
+            return Err(self.last_token.error("Expected variables, e.g. `x:int`"))
         };
 
-        tokens.into_iter().filter_map(|token| self.parse_variable(token)).collect()
+        tokens
+            .into_iter()
+            .filter_map(|token| self.parse_variable(token).transpose())
+            .collect()
     }
 
-    fn parse_variable(&mut self, token: &str) -> Option<VariableCommand> {
+    fn parse_variable(&mut self, token: Token<'a>) -> Result<Option<VariableCommand>> {
         // A token may be empty if extra spaces were present: "read   x:int  "
-        if token.is_empty() {
-            return None
+        if token.text.is_empty() {
+            return Ok(None)
         }
-        let Some((ident, type_string)) = token.split_once(':') else {
-            panic!("Variable must have type")
+        let Some((ident, type_string)) = token.text.split_once(':') else {
+            return Err(
+                token.error(format!("Variable `{}` has no type, e.g. `{}:int`", token.text, token.text))
+            )
         };
-        let (var_type, max_length) = Self::extract_type_and_length(type_string);
+        if ident.is_empty() {
+            return Err(token.error_in(0, 1, format!("Variable has no name, e.g. `x{}`", token.text)))
+        }
+        let type_start = ident.chars().count() + 1;
+        let (var_type, max_length) = Self::extract_type_and_length(type_string)
+            .map_err(|message| token.error_in(type_start, type_string.chars().count(), message))?;
+        if let Some(length) = &max_length {
+            if length.parse::<usize>().is_err() && !self.read_pairings.contains_key(length) {
+                let length_start = type_start + type_string.chars().take_while(|&ch| ch != '(').count() + 1;
+                return Err(token.error_in(
+                    length_start,
+                    length.chars().count(),
+                    format!(
+                        "Maximum length `{}` is neither a number nor a variable that was read before",
+                        length
+                    ),
+                ))
+            }
+        }
         self.read_pairings.insert(String::from(ident), var_type);
 
-        Some(VariableCommand::new(ident.to_string(), var_type, max_length))
+        Ok(Some(VariableCommand::new(ident.to_string(), var_type, max_length)))
     }
 
-    fn extract_type_and_length(type_string: &str) -> (VarType, Option<String>) {
+    /// The type and maximum length of `type_string`, or a message explaining
+    /// why it is not a type.
+    fn extract_type_and_length(type_string: &str) -> std::result::Result<(VarType, Option<String>), String> {
+        let unknown_type = |var_type: &str| {
+            format!(
+                "Unknown variable type `{}`, expected int, float, long, bool, word(N) or string(N)",
+                var_type
+            )
+        };
         match type_string.trim_end_matches(')').split_once('(') {
-            Some((var_type, max_length)) => (VarType::new_sized(var_type), Some(max_length.to_string())),
-            None => (VarType::new_unsized(type_string), None),
+            Some((var_type, max_length)) => match VarType::new_sized(var_type) {
+                Some(_) if max_length.is_empty() => Err(format!("`{}` has no maximum length", var_type)),
+                Some(sized) => Ok((sized, Some(max_length.to_string()))),
+                None if VarType::new_unsized(var_type).is_some() => {
+                    Err(format!("`{}` variables have no maximum length, use `{}`", var_type, var_type))
+                }
+                None => Err(unknown_type(var_type)),
+            },
+            None => match VarType::new_unsized(type_string) {
+                Some(var_type) => Ok((var_type, None)),
+                None if VarType::new_sized(type_string).is_some() => Err(format!(
+                    "`{}` variables need a maximum length, e.g. `{}(10)`",
+                    type_string, type_string
+                )),
+                None => Err(unknown_type(type_string)),
+            },
         }
     }
 
@@ -258,29 +437,36 @@ impl<'a> Parser<'a> {
     }
 
     fn next_token(&mut self) -> Option<&'a str> {
-        self.token_stream.next()
+        self.last_token = self.token_stream.next()?;
+        Some(self.last_token.text)
     }
 
     fn first_non_whitespace_token(&mut self) -> Option<&'a str> {
-        self.token_stream.by_ref().find(|&token| token != "\n" && !token.is_empty())
+        while let Some(token) = self.next_token() {
+            if token != "\n" && !token.is_empty() {
+                return Some(token)
+            }
+        }
+        None
     }
 
     fn rest_of_line(&mut self) -> Option<String> {
-        Some(self.tokens_upto_newline()?.join(" ").trim().to_string())
+        let tokens: Vec<&str> = self.tokens_upto_newline()?.iter().map(|token| token.text).collect();
+        Some(tokens.join(" ").trim().to_string())
     }
 
     // Consumes the newline
-    fn tokens_upto_newline(&mut self) -> Option<Vec<&'a str>> {
+    fn tokens_upto_newline(&mut self) -> Option<Vec<Token<'a>>> {
         let mut buf = Vec::new();
 
         while let Some(token) = self.next_token() {
             if token == "\n" {
                 break
             }
-            buf.push(token)
+            buf.push(self.last_token)
         }
 
-        if buf.iter().all(|s| s.is_empty()) {
+        if buf.iter().all(|token| token.text.is_empty()) {
             None
         } else {
             Some(buf)
@@ -4,62 +4,83 @@ use indoc::indoc;
 
 use super::*;
 
+/// Line, column and length of the error of a parse that should fail.
+fn error_span(result: Result<Cmd>) -> (usize, usize, usize) {
+    let error = result.expect_err("Parsing should fail");
+    (error.line, error.column, error.length)
+}
+
 #[test]
 fn parse_read_parses_variable_list() {
     let mut parser = Parser::new("a:int b:long");
-    let Cmd::Read(variables) = parser.parse_read() else { panic!() };
+    let Cmd::Read(variables) = parser.parse_read().unwrap() else { panic!() };
     assert_eq!(variables.len(), 2)
 }
 
 #[test]
-#[should_panic]
-fn parse_read_panics_without_variables() {
-    Parser::new("").parse_read();
+fn parse_read_errors_without_variables() {
+    assert_eq!(error_span(Parser::new("").parse_read()), (1, 1, 1));
+}
+
+#[test]
+fn parse_read_errors_without_variable_type() {
+    assert_eq!(error_span(Parser::new("a:int bb").parse_read()), (1, 7, 2));
+}
+
+#[test]
+fn parse_read_errors_with_variable_of_unknown_type() {
+    let error = Parser::new("a:enum").parse_read().unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (1, 3, 4));
+    assert!(error.message.starts_with("Unknown variable type `enum`"));
+}
+
+#[test]
+fn parse_read_errors_with_sized_variable_without_size() {
+    let error = Parser::new("a:word").parse_read().unwrap_err();
+    assert_eq!(error.message, "`word` variables need a maximum length, e.g. `word(10)`");
 }
 
 #[test]
-#[should_panic]
-fn parse_read_panics_without_variable_type() {
-    Parser::new("a").parse_read();
+fn parse_read_errors_with_unsized_variable_with_size() {
+    assert_eq!(error_span(Parser::new("a:word(3) bc:int(3)").parse_read()), (1, 14, 6));
 }
 
 #[test]
-#[should_panic]
-fn parse_read_panics_with_variable_of_unknown_type() {
-    Parser::new("a:enum").parse_read();
+fn parse_read_errors_with_variable_without_name() {
+    assert_eq!(error_span(Parser::new("a:int :int").parse_read()), (1, 7, 1));
 }
 
 #[test]
-#[should_panic]
-fn parse_read_panics_with_sized_variable_without_size() {
-    Parser::new("a:word").parse_read();
+fn parse_read_errors_with_unknown_length_variable() {
+    let error = Parser::new("n:int s:word(n) t:word(abc)").parse_read().unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (1, 24, 3));
+    assert!(error.message.starts_with("Maximum length `abc` is neither a number nor a variable"));
 }
 
 #[test]
 fn parse_write_captures_text() {
     let mut parser = Parser::new("hello world");
-    let Cmd::Write { lines, .. } = parser.parse_write() else { panic!() };
+    let Cmd::Write { lines, .. } = parser.parse_write().unwrap() else { panic!() };
     assert_eq!(lines[0], "hello world");
 }
 
 #[test]
 fn parse_write_captures_lines_of_text() {
     let mut parser = Parser::new("hello\nworld");
-    let Cmd::Write { lines, .. } = parser.parse_write() else { panic!() };
+    let Cmd::Write { lines, .. } = parser.parse_write().unwrap() else { panic!() };
     assert_eq!(lines, vec!["hello", "world"]);
 }
 
 #[test]
 fn parse_write_captures_lines_of_text_until_empty_line() {
     let mut parser = Parser::new("hello\nworld\n\nread");
-    let Cmd::Write { lines, .. } = parser.parse_write() else { panic!() };
+    let Cmd::Write { lines, .. } = parser.parse_write().unwrap() else { panic!() };
     assert_eq!(lines, vec!["hello", "world"]);
 }
 
 #[test]
-#[should_panic]
 fn parse_write_errors_on_write_join_with_undeclared_vars() {
-    Parser::new("join(\"hello\", world)").parse_write();
+    assert_eq!(error_span(Parser::new("join(\"hello\", world)").parse_write()), (1, 15, 5));
 }
 
 #[test]
@@ -69,8 +90,8 @@ fn parse_write_returns_write_joins() {
         join("hello", world)
     "##});
 
-    parser.parse_read();
-    let Cmd::WriteJoin { join_terms, output_comment: _} = parser.parse_write() else { panic!() };
+    parser.parse_read().unwrap();
+    let Cmd::WriteJoin { join_terms, output_comment: _} = parser.parse_write().unwrap() else { panic!() };
 
     let [
         JoinTerm { ident: first_term,  .. }, 
@@ -84,60 +105,57 @@ fn parse_write_returns_write_joins() {
 #[test]
 fn parse_write_captures_empty_write_joins() {
     let mut parser = Parser::new("hello join() world");
-    let Cmd::Write { lines, .. } = parser.parse_write() else { panic!() };
+    let Cmd::Write { lines, .. } = parser.parse_write().unwrap() else { panic!() };
     assert_eq!(lines[0], "hello join() world");
 }
 
 #[test]
 fn parse_write_captures_incomplete_write_joins() {
     let mut parser = Parser::new("hello join( world");
-    let Cmd::Write { lines, .. } = parser.parse_write() else { panic!() };
+    let Cmd::Write { lines, .. } = parser.parse_write().unwrap() else { panic!() };
     assert_eq!(lines[0], "hello join( world");
 }
 
 #[test]
 fn parse_write_captures_invalid_write_joins() {
     let mut parser = Parser::new("hello join(\"thing\",,) world");
-    let Cmd::Write { lines, .. } = parser.parse_write() else { panic!() };
+    let Cmd::Write { lines, .. } = parser.parse_write().unwrap() else { panic!() };
     assert_eq!(lines[0], "hello join(\"thing\",,) world");
 }
 
 #[test]
 fn parse_loop_accepts_literal_count() {
     let mut parser = Parser::new("2 read a:int");
-    let Cmd::Loop { count_var, .. } = parser.parse_loop() else { panic!() };
+    let Cmd::Loop { count_var, .. } = parser.parse_loop().unwrap() else { panic!() };
     assert_eq!(count_var, "2")
 }
 
 #[test]
 fn parse_loop_accepts_identifier_count() {
     let mut parser = Parser::new("n read a:int");
-    let Cmd::Loop { count_var, .. } = parser.parse_loop() else { panic!() };
+    let Cmd::Loop { count_var, .. } = parser.parse_loop().unwrap() else { panic!() };
     assert_eq!(count_var, "n")
 }
 
 #[test]
-#[should_panic]
-fn parse_loop_panics_without_identifier() {
-    Parser::new("read a:int").parse_loop();
+fn parse_loop_errors_without_identifier() {
+    assert_eq!(error_span(Parser::new("read a:int").parse_loop()), (1, 6, 5));
 }
 
 #[test]
-#[should_panic]
-fn parse_loop_panics_without_command() {
-    Parser::new("n").parse_loop();
+fn parse_loop_errors_without_command() {
+    assert_eq!(error_span(Parser::new("n").parse_loop()), (1, 2, 1));
 }
 
 #[test]
-#[should_panic]
-fn parse_loop_panics_with_unknown_command() {
-    Parser::new("n dance").parse_loop();
+fn parse_loop_errors_with_unknown_command() {
+    assert_eq!(error_span(Parser::new("n\n  dance").parse_loop()), (2, 3, 5));
 }
 
 #[test]
 fn parse_loop_accepts_read_command() {
     let mut parser = Parser::new("n read a:int b:long c:bool");
-    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop() else { panic!() };
+    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop().unwrap() else { panic!() };
     let Cmd::Read(vars) = *inner_cmd else { panic!() };
     assert_eq!(vars.len(), 3)
 }
@@ -145,7 +163,7 @@ fn parse_loop_accepts_read_command() {
 #[test]
 fn parse_loop_accepts_write_command() {
     let mut parser = Parser::new("n write hello world");
-    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop() else { panic!() };
+    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop().unwrap() else { panic!() };
     let Cmd::Write { lines, .. } = *inner_cmd else { panic!() };
     assert_eq!(lines[0], "hello world")
 }
@@ -153,7 +171,7 @@ fn parse_loop_accepts_write_command() {
 #[test]
 fn parse_loop_accepts_loopline() {
     let mut parser = Parser::new("n loopline 3 x:int");
-    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop() else { panic!() };
+    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop().unwrap() else { panic!() };
     let Cmd::LoopLine { count_var, variables } = *inner_cmd else { panic!() };
     assert_eq!(count_var, "3");
     assert_eq!(variables.len(), 1);
@@ -163,7 +181,7 @@ fn parse_loop_accepts_loopline() {
 fn parse_loop_can_be_nested_infinitely() {
     let stub_text = "n loop ".repeat(20) + "n read a:int";
     let mut parser = Parser::new(stub_text.as_str());
-    let mut current_cmd = parser.parse_loop();
+    let mut current_cmd = parser.parse_loop().unwrap();
     while let Cmd::Loop { command: inner_cmd, count_var  } = current_cmd {
         current_cmd = *inner_cmd;
         assert_eq!(count_var, "n");
@@ -175,7 +193,7 @@ fn parse_loop_can_be_nested_infinitely() {
 #[test]
 fn parse_loop_tolerates_newlines_around_count() {
     let mut parser = Parser::new(" \nn \nread x:int");
-    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop() else { panic!() };
+    let Cmd::Loop { command: inner_cmd, ..  } = parser.parse_loop().unwrap() else { panic!() };
     let Cmd::Read(vars) = *inner_cmd else { panic!() };
     assert_eq!(vars.len(), 1);
 }
@@ -183,21 +201,30 @@ fn parse_loop_tolerates_newlines_around_count() {
 #[test]
 fn parse_loopline_parses_counter_and_variables() {
     let mut parser = Parser::new("n a:int b:long c:word(50)");
-    let Cmd::LoopLine { count_var, variables } = parser.parse_loopline() else { panic!() };
+    let Cmd::LoopLine { count_var, variables } = parser.parse_loopline().unwrap() else { panic!() };
     assert_eq!(count_var, "n");
     assert_eq!(variables.len(), 3);
 }
 
 #[test]
-#[should_panic]
-fn parse_loopline_panics_without_counter() {
-    Parser::new("").parse_loopline();
+fn parse_loopline_errors_without_counter() {
+    assert_eq!(error_span(Parser::new("").parse_loopline()), (1, 1, 1));
+}
+
+#[test]
+fn parse_loopline_errors_without_variables() {
+    assert_eq!(error_span(Parser::new("n").parse_loopline()), (1, 2, 1));
 }
 
 #[test]
-#[should_panic]
-fn parse_loopline_panics_without_variables() {
-    Parser::new("n").parse_loopline();
+fn parse_errors_on_unknown_command() {
+    let error = Parser::new("read n:int\n\nwrite x\n\ndance").parse().unwrap_err();
+    assert_eq!((error.line, error.column, error.length), (5, 1, 5));
+    assert_eq!(
+        error.render("read n:int\n\nwrite x\n\ndance"),
+        "Unknown command `dance`, expected read, write, loop, loopline, INPUT, OUTPUT or STATEMENT\n \
+          --> line 5, column 1\n  |\n5 | dance\n  | ^^^^^"
+    );
 }
 
 #[test]
@@ -208,7 +235,7 @@ fn parse_input_comment_attaches_comment_to_read() {
         a: a number
     "});
 
-    let mut commands = [parser.parse_read()];
+    let mut commands = [parser.parse_read().unwrap()];
     parser.parse_input_comment(&mut commands);
     let Cmd::Read(ref vars) = commands[0] else { panic!() };
     assert_eq!(vars[0].input_comment, "a number");
@@ -223,7 +250,7 @@ fn parse_input_comment_attaches_comment_to_multiple_vars() {
         a: a number
     "});
 
-    let mut commands = [parser.parse_read()];
+    let mut commands = [parser.parse_read().unwrap()];
     parser.parse_input_comment(&mut commands);
     let Cmd::Read(ref vars) = commands[0] else { panic!() };
     assert_eq!(vars[0].input_comment, "a number");
@@ -239,7 +266,7 @@ fn parse_input_comment_ignores_lines_without_variable() {
         a: a number
     "});
 
-    let mut commands = [parser.parse_read()];
+    let mut commands = [parser.parse_read().unwrap()];
     parser.parse_input_comment(&mut commands);
     let Cmd::Read(ref vars) = commands[0] else { panic!() };
     assert_eq!(vars[0].input_comment, "a number");
@@ -253,7 +280,7 @@ fn parse_input_comment_attaches_comment_to_loopline() {
         a: a number
     "});
 
-    let mut commands = [parser.parse_loopline()];
+    let mut commands = [parser.parse_loopline().unwrap()];
     parser.parse_input_comment(&mut commands);
     let Cmd::LoopLine { ref variables, .. } = commands[0] else { panic!() };
     assert_eq!(variables[0].input_comment, "a number");
@@ -266,7 +293,7 @@ fn parse_input_comment_attaches_comment_to_read_inside_loop() {
         INPUT
         a: a number
     "});
-    let mut commands = [parser.parse_loop()];
+    let mut commands = [parser.parse_loop().unwrap()];
     parser.parse_input_comment(&mut commands);
     let Cmd::Loop { ref command, .. } = commands[0] else { panic!() };
     let Cmd::Read(variables) = *command.clone() else { panic!() };
@@ -281,7 +308,7 @@ fn parse_input_comment_attaches_comment_to_loopline_inside_loop() {
         a: a number
     "});
 
-    let mut commands = [parser.parse_loop()];
+    let mut commands = [parser.parse_loop().unwrap()];
     parser.parse_input_comment(&mut commands);
     let Cmd::Loop { ref command, .. } = commands[0] else { panic!() };
     let Cmd::LoopLine { ref variables, .. } = *command.clone() else { panic!() };
@@ -297,7 +324,7 @@ fn parse_output_comment_adds_comment_to_write() {
         Mama said
     "});
 
-    let mut commands = [parser.parse_write()];
+    let mut commands = [parser.parse_write().unwrap()];
     parser.parse_output_comment(&mut commands);
     let Cmd::Write { ref lines, ref output_comment } = commands[0] else { panic!() };
     assert_eq!(lines[0], "Knock You Out");
@@ -315,7 +342,7 @@ fn parse_output_comment_adds_comment_to_multiple_writes() {
         Mama said
     "});
 
-    let mut commands = [parser.parse_write(), parser.parse_write()];
+    let mut commands = [parser.parse_write().unwrap(), parser.parse_write().unwrap()];
     parser.parse_output_comment(&mut commands);
 
     let Cmd::Write { ref lines, ref output_comment } = commands[0] else { panic!() };
@@ -340,7 +367,7 @@ fn parse_output_comment_does_not_overwrite() {
         Daddy said
     "});
 
-    let mut commands = [parser.parse_write()];
+    let mut commands = [parser.parse_write().unwrap()];
     parser.parse_output_comment(&mut commands);
     parser.parse_output_comment(&mut commands); // Parses "Daddy said" but does not use it
 
@@ -358,7 +385,7 @@ fn parse_output_comment_adds_comment_to_write_join() {
         Mama said
     "##});
 
-    let mut commands = [parser.parse_write()];
+    let mut commands = [parser.parse_write().unwrap()];
     parser.parse_output_comment(&mut commands);
     let Cmd::WriteJoin { ref output_comment, .. } = commands[0] else { panic!() };
     assert_eq!(output_comment[0], "Mama said");